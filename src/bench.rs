@@ -0,0 +1,169 @@
+use std::time::{Duration, Instant};
+
+use wgpu_bootstrap::{default::Vertex, wgpu};
+
+use crate::cloth_grid::ClothGridBuilder;
+use crate::gpu::Gpu;
+use crate::headless::HeadlessContext;
+use crate::{spring_offsets, to_vec4, Spring, Velocity, CLOTH_MATERIAL};
+
+// square grids of that many vertices per side, 512 x 512 is 262144 vertices and 3 million springs
+const GRID_SIZES: [u32; 4] = [64, 128, 256, 512];
+const WARMUP_STEPS: u32 = 10; // not measured, the driver compiles the pipeline and allocates the buffers
+
+pub struct BenchOptions {
+    pub steps: u32,
+    pub software: bool, // ask for the fallback (CPU) adapter, as --headless
+}
+
+// "--bench <steps>" times that many steps of the spring loop on each grid, once with each layout of the state
+pub const OPTIONS: &[(&str, bool)] = &[("--bench", true), ("--software", false)];
+
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Option<BenchOptions> {
+    let mut steps = None;
+    let mut software = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bench" => steps = Some(args.next().and_then(|value| value.parse().ok()).expect("--bench needs a number of steps")),
+            "--software" => software = true,
+            _ => {}
+        }
+    }
+    steps.map(|steps| BenchOptions { steps, software })
+}
+
+// where the shader finds the positions and the velocities
+#[derive(Clone, Copy)]
+pub enum Layout {
+    Vertex, // the whole Vertex and 3 floats of velocity, as before the vec4 buffers
+    Vec4, // a vec4 buffer for each, as the simulation does now
+}
+
+impl Layout {
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::Vertex => "Vertex + Velocity",
+            Layout::Vec4 => "vec4 + vec4",
+        }
+    }
+
+    // bytes of a position and a velocity on the GPU
+    fn state_size(self) -> usize {
+        match self {
+            Layout::Vertex => std::mem::size_of::<Vertex>() + std::mem::size_of::<Velocity>(),
+            Layout::Vec4 => 2 * std::mem::size_of::<[f32; 4]>(),
+        }
+    }
+
+    fn shader(self) -> String {
+        let header = match self {
+            Layout::Vertex => include_str!("bench_vertex.wgsl"),
+            Layout::Vec4 => include_str!("bench_vec4.wgsl"),
+        };
+        format!("{}{}", header, include_str!("bench_springs.wgsl"))
+    }
+
+    // the two buffers of the state, positions and velocities, with the cloth at rest
+    fn state_buffers(self, context: &impl Gpu, vertices: &[Vertex]) -> [wgpu::Buffer; 2] {
+        match self {
+            Layout::Vertex => {
+                let velocities = vec![Velocity { velocity: [0.0, 0.0, 0.0] }; vertices.len()];
+                [
+                    context.create_buffer(vertices, wgpu::BufferUsages::STORAGE),
+                    context.create_buffer(&velocities, wgpu::BufferUsages::STORAGE),
+                ]
+            }
+            Layout::Vec4 => [
+                context.create_buffer(&to_vec4(vertices.iter().map(|vertex| vertex.position), 1.0), wgpu::BufferUsages::STORAGE),
+                context.create_buffer(&vec![[0.0f32; 4]; vertices.len()], wgpu::BufferUsages::STORAGE),
+            ],
+        }
+    }
+}
+
+pub struct BenchResult {
+    pub grid_size: u32,
+    pub layout: Layout,
+    pub step_time: Duration,
+    // bytes of state requested by the shader in a step: each vertex reads its own position and velocity and both ends of its springs.
+    // it is not the memory traffic, the caches of the GPU make that smaller, but it's the same count for both layouts
+    pub state_requested: usize,
+}
+
+// average time of a step of one layout on one cloth
+fn bench_layout(context: &impl Gpu, layout: Layout, vertices: &[Vertex], springs: &[Spring], steps: u32) -> Duration {
+    let offsets = spring_offsets(springs, vertices.len());
+
+    let pipeline = context.create_compute_pipeline("Bench Pipeline", &layout.shader());
+    // two copies of the state like in MyApp, a step reads one and writes the other
+    let states = [0, 1].map(|_| layout.state_buffers(context, vertices));
+    let state_bind_group = |group: u32, state: usize| context.create_bind_group(
+        "Bench State Bind Group",
+        &pipeline.get_bind_group_layout(group),
+        &[
+            wgpu::BindGroupEntry { binding: 0, resource: states[state][0].as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: states[state][1].as_entire_binding() },
+        ],
+    );
+    let start_bind_groups = [0, 1].map(|state| state_bind_group(0, state));
+    let end_bind_groups = [0, 1].map(|state| state_bind_group(1, state));
+
+    let springs_buffer = context.create_buffer(springs, wgpu::BufferUsages::STORAGE);
+    let offsets_buffer = context.create_buffer(&offsets, wgpu::BufferUsages::STORAGE);
+    let springs_bind_group = context.create_bind_group(
+        "Bench Springs Bind Group",
+        &pipeline.get_bind_group_layout(2),
+        &[
+            wgpu::BindGroupEntry { binding: 0, resource: springs_buffer.as_entire_binding() },
+            wgpu::BindGroupEntry { binding: 1, resource: offsets_buffer.as_entire_binding() },
+        ],
+    );
+
+    // one submit per step like MyApp::step, then we wait for the GPU to be done
+    let run_steps = |first_step: u32, count: u32| {
+        for step in first_step..first_step + count {
+            let front = (step % 2) as usize;
+            let mut encoder = context.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Bench Encoder"),
+            });
+            {
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Bench Pass"),
+                });
+                compute_pass.set_pipeline(&pipeline);
+                compute_pass.set_bind_group(0, &start_bind_groups[front], &[]);
+                compute_pass.set_bind_group(1, &end_bind_groups[1 - front], &[]);
+                compute_pass.set_bind_group(2, &springs_bind_group, &[]);
+                compute_pass.dispatch_workgroups((vertices.len() as f32 / 128.0).ceil() as u32, 1, 1);
+            }
+            context.queue().submit(Some(encoder.finish()));
+        }
+        context.device().poll(wgpu::Maintain::Wait);
+    };
+
+    run_steps(0, WARMUP_STEPS);
+    let start = Instant::now();
+    run_steps(WARMUP_STEPS, steps);
+    start.elapsed() / steps.max(1)
+}
+
+pub fn run(options: &BenchOptions) -> Result<Vec<BenchResult>, String> {
+    let context = HeadlessContext::new(64, 64, options.software)?;
+    let mut results = Vec::new();
+    for grid_size in GRID_SIZES {
+        let grid = ClothGridBuilder::new(grid_size, grid_size);
+        let vertices = grid.vertices();
+        let springs = grid.springs(&vec![CLOTH_MATERIAL; vertices.len()]);
+        let state_reads = vertices.len() + 2 * springs.len();
+        for layout in [Layout::Vertex, Layout::Vec4] {
+            let step_time = bench_layout(&context, layout, &vertices, &springs, options.steps);
+            results.push(BenchResult {
+                grid_size,
+                layout,
+                step_time,
+                state_requested: state_reads * layout.state_size(),
+            });
+        }
+    }
+    Ok(results)
+}
@@ -0,0 +1,37 @@
+// benchmark of the layout of the state: the spring loop of forces_compute.wgsl followed by the integration of compute.wgsl, in one pass.
+// bench.rs puts bench_vertex.wgsl or bench_vec4.wgsl in front of this file, they say where the positions and the velocities are
+// (vertex_position, vertex_velocity and write_state), the rest of the work is the same
+
+struct Spring {
+    vertex_index_1: u32,
+    vertex_index_2: u32,
+    rest_length: f32,
+    stiffness: f32,
+    damping: f32,
+    spring_type: u32,
+}
+
+let DELTA_TIME: f32 = 0.0001; // petit pour que le tissu ne parte pas ailleurs pendant la mesure
+let VERTEX_MASS: f32 = 1.0;
+
+@group(2) @binding(0) var<storage, read> springsR: array<Spring>;
+@group(2) @binding(1) var<storage, read> springOffsets: array<u32>; // the same as spring_offsets in main.rs
+
+@compute @workgroup_size(128, 1, 1)
+fn main(@builtin(global_invocation_id) param: vec3<u32>) {
+    if (param.x >= arrayLength(&springOffsets) - 1u) {
+          return;
+    }
+
+    var force_sum = vec3<f32>(0.0, -9.81 * VERTEX_MASS, 0.0);
+    for (var spring_index = springOffsets[param.x]; spring_index < springOffsets[param.x + 1u]; spring_index++) {
+        let spring = springsR[spring_index];
+        let difference = vertex_position(spring.vertex_index_1) - vertex_position(spring.vertex_index_2);
+        let distance = length(difference);
+        force_sum += -spring.stiffness * (distance - spring.rest_length) * normalize(difference);
+        force_sum += -spring.damping * (vertex_velocity(spring.vertex_index_1) - vertex_velocity(spring.vertex_index_2));
+    }
+
+    let velocity = vertex_velocity(param.x) + (force_sum / VERTEX_MASS) * DELTA_TIME;
+    write_state(param.x, vertex_position(param.x) + velocity * DELTA_TIME, velocity);
+}
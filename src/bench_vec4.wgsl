@@ -0,0 +1,20 @@
+// the state as the simulation keeps it: one vec4 per vertex for the positions (w = 1) and the velocities (w = 0)
+
+@group(0) @binding(0) var<storage, read> startPositions: array<vec4<f32>>;
+@group(0) @binding(1) var<storage, read> startVelocities: array<vec4<f32>>;
+@group(1) @binding(0) var<storage, read_write> verticiesPositions: array<vec4<f32>>;
+@group(1) @binding(1) var<storage, read_write> verticiesVelocities: array<vec4<f32>>;
+
+fn vertex_position(index: u32) -> vec3<f32> {
+    return startPositions[index].xyz;
+}
+
+fn vertex_velocity(index: u32) -> vec3<f32> {
+    return startVelocities[index].xyz;
+}
+
+fn write_state(index: u32, position: vec3<f32>, velocity: vec3<f32>) {
+    verticiesPositions[index] = vec4<f32>(position, 1.0);
+    verticiesVelocities[index] = vec4<f32>(velocity, 0.0);
+}
+
@@ -0,0 +1,45 @@
+// the state as it was before the vec4 buffers: the whole Vertex of wgpu-bootstrap (11 floats) for the position
+// and 3 floats for the velocity, every read fetches the struct to use 3 of its floats
+
+struct Position {
+    position_x: f32,
+    position_y: f32,
+    position_z: f32,
+    normal_x: f32,
+    normal_y: f32,
+    normal_z: f32,
+    tangent_x: f32,
+    tangent_y: f32,
+    tangent_z: f32,
+    tex_coords_x: f32,
+    tex_coords_y: f32,
+}
+
+struct Velocity {
+    velocity_x: f32,
+    velocity_y: f32,
+    velocity_z: f32,
+}
+
+@group(0) @binding(0) var<storage, read> startPositions: array<Position>;
+@group(0) @binding(1) var<storage, read> startVelocities: array<Velocity>;
+@group(1) @binding(0) var<storage, read_write> verticiesPositions: array<Position>;
+@group(1) @binding(1) var<storage, read_write> verticiesVelocities: array<Velocity>;
+
+fn vertex_position(index: u32) -> vec3<f32> {
+    return vec3<f32>(startPositions[index].position_x, startPositions[index].position_y, startPositions[index].position_z);
+}
+
+fn vertex_velocity(index: u32) -> vec3<f32> {
+    return vec3<f32>(startVelocities[index].velocity_x, startVelocities[index].velocity_y, startVelocities[index].velocity_z);
+}
+
+fn write_state(index: u32, position: vec3<f32>, velocity: vec3<f32>) {
+    verticiesPositions[index].position_x = position.x;
+    verticiesPositions[index].position_y = position.y;
+    verticiesPositions[index].position_z = position.z;
+    verticiesVelocities[index].velocity_x = velocity.x;
+    verticiesVelocities[index].velocity_y = velocity.y;
+    verticiesVelocities[index].velocity_z = velocity.z;
+}
+
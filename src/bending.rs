@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use wgpu_bootstrap::{
+    cgmath::{self, InnerSpace},
+    default::Vertex,
+};
+
+use crate::material::{Material, SpringKind};
+
+// two triangles sharing an edge: vertex_0 and vertex_1 are the ends of the edge,
+// vertex_2 and vertex_3 are the vertices opposite to the edge in the first and the second triangle
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Hinge {
+    pub vertex_0: u32,
+    pub vertex_1: u32,
+    pub vertex_2: u32,
+    pub vertex_3: u32,
+    pub rest_angle: f32, // angle between the two triangles in the initial mesh, 0 when they are flat
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+fn position(vertices: &[Vertex], index: u32) -> cgmath::Vector3<f32> {
+    cgmath::Vector3::from(vertices[index as usize].position)
+}
+
+// signed angle between the normals of the two triangles, the same formula as in forces_compute.wgsl
+fn dihedral_angle(vertices: &[Vertex], hinge: &Hinge) -> f32 {
+    let x0 = position(vertices, hinge.vertex_0);
+    let edge = position(vertices, hinge.vertex_1) - x0;
+    let normal_a = edge.cross(position(vertices, hinge.vertex_2) - x0).normalize();
+    let normal_b = (position(vertices, hinge.vertex_3) - x0).cross(edge).normalize();
+    normal_a.cross(normal_b).dot(edge.normalize()).atan2(normal_a.dot(normal_b))
+}
+
+// one hinge for each edge shared by two triangles, with the rest angle of the initial mesh
+pub fn build_hinges(vertices: &[Vertex], indices: &[u32], vertex_materials: &[Material]) -> Vec<Hinge> {
+    // for each edge, the ordered edge and the opposite vertex of the triangles that use it
+    let mut edges: HashMap<(u32, u32), Vec<[u32; 3]>> = HashMap::new();
+    for triangle in indices.chunks_exact(3) {
+        for corner in 0..3 {
+            let vertex_1 = triangle[corner];
+            let vertex_2 = triangle[(corner + 1) % 3];
+            let opposite = triangle[(corner + 2) % 3];
+            edges.entry((vertex_1.min(vertex_2), vertex_1.max(vertex_2))).or_default().push([vertex_1, vertex_2, opposite]);
+        }
+    }
+
+    let mut hinges = Vec::new();
+    for triangles in edges.values() {
+        if let [[vertex_0, vertex_1, vertex_2], [_, _, vertex_3]] = triangles[..] {
+            // the hinge is as stiff as the mean of the materials of its four vertices
+            let (stiffness, damping) = [vertex_0, vertex_1, vertex_2, vertex_3]
+                .iter()
+                .map(|&vertex| vertex_materials[vertex as usize].spring_parameters(SpringKind::Bend))
+                .fold((0.0, 0.0), |(stiffness, damping), (s, d)| (stiffness + s / 4.0, damping + d / 4.0));
+
+            let mut hinge = Hinge {
+                vertex_0,
+                vertex_1,
+                vertex_2,
+                vertex_3,
+                rest_angle: 0.0,
+                stiffness,
+                damping,
+            };
+            hinge.rest_angle = dihedral_angle(vertices, &hinge);
+            hinges.push(hinge);
+        }
+    }
+
+    hinges.sort_by_key(|hinge| (hinge.vertex_0, hinge.vertex_1));
+    hinges
+}
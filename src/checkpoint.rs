@@ -0,0 +1,156 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+
+use bytemuck::Pod;
+use wgpu_bootstrap::default::Vertex;
+
+use crate::{ComputeData, Spring, Velocity};
+
+// the whole state of a simulation at one step, enough to go on from there as if it had never stopped.
+// the springs keep their torn types and plastic rest lengths, the indices the triangles removed by the tears,
+// and the compute data the sphere collider and the grab of that step
+pub struct Checkpoint {
+    pub compute_data: ComputeData,
+    pub time: f32,
+    pub frame_count: u32,
+    pub vertices: Vec<Vertex>,
+    pub velocities: Vec<Velocity>,
+    pub springs: Vec<Spring>,
+    pub indices: Vec<u32>,
+}
+
+// file: "CLTHSTAT", version u32, then every field in order, a list being its length (u32) followed by its raw content
+const MAGIC: &[u8; 8] = b"CLTHSTAT";
+const VERSION: u32 = 1;
+
+fn write_values<T: Pod>(writer: &mut impl Write, values: &[T]) -> io::Result<()> {
+    writer.write_all(&(values.len() as u32).to_le_bytes())?;
+    writer.write_all(bytemuck::cast_slice(values))
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "truncated checkpoint")
+}
+
+// the length is checked against what is left before `end` so a damaged file can't ask for gigabytes
+fn read_values<T: Pod>(reader: &mut (impl Read + Seek), end: u64) -> io::Result<Vec<T>> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as u64;
+    if length * std::mem::size_of::<T>() as u64 > end.saturating_sub(reader.stream_position()?) {
+        return Err(truncated());
+    }
+    let mut values = vec![T::zeroed(); length as usize];
+    reader.read_exact(bytemuck::cast_slice_mut(&mut values))?;
+    Ok(values)
+}
+
+// the fields that are a single value are written as a list of one
+fn single<T>(values: Vec<T>) -> io::Result<T> {
+    values.into_iter().next().ok_or_else(truncated)
+}
+
+impl Checkpoint {
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        if let Some(directory) = std::path::Path::new(path).parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)?;
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+
+        file.write_all(MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        write_values(&mut file, &[self.compute_data])?;
+        write_values(&mut file, &[self.time])?;
+        write_values(&mut file, &[self.frame_count])?;
+        write_values(&mut file, &self.vertices)?;
+        write_values(&mut file, &self.velocities)?;
+        write_values(&mut file, &self.springs)?;
+        write_values(&mut file, &self.indices)?;
+        file.flush()
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let end = file.get_ref().metadata()?.len();
+
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)?;
+        let mut version = [0; 4];
+        file.read_exact(&mut version)?;
+        if &magic != MAGIC || u32::from_le_bytes(version) != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a checkpoint of this version of the simulation"));
+        }
+
+        Ok(Self {
+            compute_data: single(read_values(&mut file, end)?)?,
+            time: single(read_values(&mut file, end)?)?,
+            frame_count: single(read_values(&mut file, end)?)?,
+            vertices: read_values(&mut file, end)?,
+            velocities: read_values(&mut file, end)?,
+            springs: read_values(&mut file, end)?,
+            indices: read_values(&mut file, end)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint() -> Checkpoint {
+        Checkpoint {
+            compute_data: bytemuck::Zeroable::zeroed(),
+            time: 1.5,
+            frame_count: 90,
+            vertices: vec![bytemuck::Zeroable::zeroed(); 4],
+            velocities: vec![Velocity { velocity: [0.0, -1.0, 0.0] }; 4],
+            springs: vec![bytemuck::Zeroable::zeroed(); 6],
+            indices: vec![0, 1, 2, 1, 3, 2],
+        }
+    }
+
+    fn path(name: &str) -> String {
+        std::env::temp_dir().join(format!("cloth_checkpoint_{}_{}.state", name, std::process::id())).to_str().unwrap().to_string()
+    }
+
+    fn load_error(name: &str, bytes: &[u8]) -> io::ErrorKind {
+        let path = path(name);
+        fs::write(&path, bytes).unwrap();
+        let kind = Checkpoint::load(&path).err().expect("the checkpoint should be refused").kind();
+        fs::remove_file(&path).unwrap();
+        kind
+    }
+
+    #[test]
+    fn a_checkpoint_is_read_back() {
+        let path = path("round_trip");
+        checkpoint().save(&path).unwrap();
+        let loaded = Checkpoint::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!((loaded.time, loaded.frame_count), (1.5, 90));
+        assert_eq!((loaded.vertices.len(), loaded.velocities.len(), loaded.springs.len()), (4, 4, 6));
+        assert_eq!(loaded.indices, checkpoint().indices);
+    }
+
+    #[test]
+    fn a_damaged_checkpoint_is_refused() {
+        let path = path("damaged");
+        checkpoint().save(&path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let header = MAGIC.len() + 4;
+        let time = header + 4 + std::mem::size_of::<ComputeData>();
+
+        // cut in the middle of the springs
+        assert_eq!(load_error("cut", &bytes[..bytes.len() - 30]), io::ErrorKind::InvalidData);
+        // a length far longer than the file
+        let mut huge = bytes.clone();
+        huge[header..header + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(load_error("huge", &huge), io::ErrorKind::InvalidData);
+        // an empty list where the time should be
+        let mut no_time = bytes[..time].to_vec();
+        no_time.extend_from_slice(&0u32.to_le_bytes());
+        no_time.extend_from_slice(&bytes[time + 8..]);
+        assert_eq!(load_error("no_time", &no_time), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use image::codecs::gif::{GifEncoder, Repeat};
+
+use crate::gpu::Gpu;
+use crate::offscreen::OffscreenTarget;
+use crate::MyApp;
+
+// vitesse de la quantification des palettes du GIF, de 1 (lent, meilleures couleurs) à 30
+const GIF_QUANTIZATION_SPEED: i32 = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClipFormat {
+    Gif,
+    Apng,
+}
+
+impl ClipFormat {
+    // the extension of the path chooses the format, ".png" and ".apng" give an APNG, anything else a GIF
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("png") || extension.eq_ignore_ascii_case("apng") => ClipFormat::Apng,
+            _ => ClipFormat::Gif,
+        }
+    }
+}
+
+// records what the viewer shows for a given duration and writes it as an animated GIF or APNG
+pub struct ClipRecorder {
+    path: PathBuf,
+    format: ClipFormat,
+    pub duration: f32, // seconds of simulation recorded
+    pub fps: f32,
+    time: f32,
+    next_capture: f32,
+    target: Option<OffscreenTarget>, // created at the first frame, with the size of the window at that time
+    frames: Vec<Vec<u8>>,
+}
+
+impl ClipRecorder {
+    pub fn new(path: &str, duration: f32, fps: f32) -> Self {
+        let path = PathBuf::from(path);
+        ClipRecorder {
+            format: ClipFormat::from_path(&path),
+            path,
+            duration,
+            fps,
+            time: 0.0,
+            next_capture: 0.0,
+            target: None,
+            frames: Vec::new(),
+        }
+    }
+
+    // the simulation went forward by delta_time, draw the app offscreen and keep the image if a frame of the clip is due.
+    // returns true once the duration is recorded
+    pub fn capture(&mut self, context: &impl Gpu, app: &MyApp, delta_time: f32) -> bool {
+        if self.time >= self.next_capture {
+            let target = self.target.get_or_insert_with(|| {
+                let (width, height) = context.size();
+                OffscreenTarget::new(context.device(), width, height, context.color_format())
+            });
+            self.frames.push(target.render(context.device(), context.queue(), app));
+            self.next_capture += 1.0 / self.fps;
+        }
+        self.time += delta_time;
+        self.time >= self.duration
+    }
+
+    pub fn number_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    // quantize and encode the recorded frames, this can take a few seconds for a GIF
+    pub fn write(self) -> io::Result<PathBuf> {
+        let target = self.target.as_ref().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no frame recorded"))?;
+        let (width, height) = (target.width, target.height);
+
+        if let Some(directory) = self.path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)?;
+        }
+        let file = BufWriter::new(File::create(&self.path)?);
+        let delay_ms = (1000.0 / self.fps).round() as u32;
+
+        match self.format {
+            ClipFormat::Gif => {
+                let mut encoder = GifEncoder::new_with_speed(file, GIF_QUANTIZATION_SPEED);
+                encoder.set_repeat(Repeat::Infinite).map_err(io::Error::other)?;
+                // chaque frame a sa propre palette de 256 couleurs, calculée par NeuQuant
+                let frames = self.frames.into_iter().map(|pixels| {
+                    let buffer = image::RgbaImage::from_raw(width, height, pixels).expect("frame of the wrong size");
+                    image::Frame::from_parts(buffer, 0, 0, image::Delay::from_numer_denom_ms(delay_ms, 1))
+                });
+                encoder.encode_frames(frames).map_err(io::Error::other)?;
+            }
+            ClipFormat::Apng => {
+                let mut encoder = png::Encoder::new(file, width, height);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_animated(self.frames.len() as u32, 0).map_err(io::Error::other)?; // 0 : en boucle
+                encoder.set_frame_delay(delay_ms.min(u16::MAX as u32) as u16, 1000).map_err(io::Error::other)?;
+                let mut writer = encoder.write_header().map_err(io::Error::other)?;
+                for pixels in &self.frames {
+                    writer.write_image_data(pixels).map_err(io::Error::other)?;
+                }
+                writer.finish().map_err(io::Error::other)?;
+            }
+        }
+
+        Ok(self.path)
+    }
+}
+
+// "--record <path>" records a clip from the start, "--record-seconds" and "--record-fps" change its length and frame rate
+pub const OPTIONS: &[(&str, bool)] = &[("--record", true), ("--record-seconds", true), ("--record-fps", true)];
+
+pub fn parse_args(mut args: impl Iterator<Item = String>, default_duration: f32, default_fps: f32) -> Option<ClipRecorder> {
+    let mut path = None;
+    let mut duration = default_duration;
+    let mut fps = default_fps;
+
+    while let Some(arg) = args.next() {
+        // 0 would give an empty clip, or one frame for ever with the frame rate
+        let mut positive = |message: &str| match args.next().and_then(|value| value.parse::<f32>().ok()) {
+            Some(value) if value > 0.0 && value.is_finite() => value,
+            _ => panic!("{}", message),
+        };
+        match arg.as_str() {
+            "--record" => path = args.next(),
+            "--record-seconds" => duration = positive("--record-seconds needs a number of seconds above 0"),
+            "--record-fps" => fps = positive("--record-fps needs a number of frames per second above 0"),
+            _ => {}
+        }
+    }
+
+    path.map(|path| ClipRecorder::new(&path, duration, fps))
+}
@@ -7,17 +7,31 @@ struct CameraUniform {
 @group(1) @binding(0)
 var<uniform> matrices: CameraUniform;
 
+// texture or heat map, the same as RenderSettings in heatmap.rs
+struct RenderSettings {
+    mode: u32,
+    max_strain: f32,
+    ramp_size: u32,
+    legend_tick: f32,
+    ramp: array<vec4<f32>, 8>,
+};
+@group(2) @binding(0)
+var<uniform> settings: RenderSettings;
+
+// la position du vertex buffer 0 n'est jamais mise à jour, celle de la simulation arrive par un troisième vertex buffer (le buffer de positions du dernier step)
 struct VertexInput {
-    @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) tangent: vec3<f32>,
     @location(3) tex_coords: vec2<f32>, // coordonnées des textures qu'on va bind sur les triangles
+    @location(4) strain: f32, // plus grand étirement des springs du sommet, dans un deuxième vertex buffer
+    @location(5) position: vec4<f32>, // w = 1
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>, //ca veut dire que c'est un vertex ............   the x and y of clip_position would be between 0-800 and 0-600 respectively with the y = 0 being the top of the screen. 
     @location(0) tex_coords: vec2<f32>, // The @location(0) bit tells WGPU to store the vec4 value returned by this function in the first color target. We'll get into what this is later.
     @location(1) normal: vec3<f32>,
+    @location(2) strain: f32,
 }
 
 @vertex // We are using @vertex to mark this function as a valid entry point for a vertex shader. We expect a u32 called in_vertex_index which gets its value from @builtin(vertex_index).
@@ -26,8 +40,9 @@ fn vs_main(
 ) -> VertexOutput {
     var out: VertexOutput;
     out.tex_coords = model.tex_coords;
-    out.clip_position = matrices.proj * matrices.view * vec4<f32>(model.position, 1.0);
+    out.clip_position = matrices.proj * matrices.view * model.position;
     out.normal = model.normal;
+    out.strain = model.strain;
     return out;
 }
 
@@ -37,7 +52,19 @@ var t_diffuse: texture_2d<f32>;
 @group(0) @binding(1)
 var s_diffuse: sampler;
 
+// colour of a strain on the ramp, the colours are evenly spaced between 0 and max_strain
+fn ramp_color(strain: f32) -> vec4<f32> {
+    let position = clamp(strain / settings.max_strain, 0.0, 1.0) * f32(settings.ramp_size - 1u);
+    let index = min(u32(position), settings.ramp_size - 2u);
+    return mix(settings.ramp[index], settings.ramp[index + 1u], position - f32(index));
+}
+
 @fragment // c'est le fragment qui associe à chaque pixel du vertex une couleur
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords); // tout ses parametre sont en lien avec la camera
+    // textureSample doit être appelé hors du if
+    let color = textureSample(t_diffuse, s_diffuse, in.tex_coords); // tout ses parametre sont en lien avec la camera
+    if settings.mode == 1u {
+        return ramp_color(in.strain);
+    }
+    return color;
 }
\ No newline at end of file
@@ -0,0 +1,324 @@
+use wgpu_bootstrap::default::Vertex;
+
+use crate::material::{self, Material, SpringKind};
+use crate::{Spring, SPRING_BEND, SPRING_SHEAR, SPRING_STRUCTURAL};
+
+// a rectangular cloth of rows x columns vertices, its triangles and its springs.
+// the rows are stacked along x and each row goes along z, the cloth is flat at the height of its center
+pub struct ClothGridBuilder {
+    rows: u32, // the minimum is 2
+    columns: u32, // number of vertices in a row, the minimum is 2
+    width: f32, // size along z
+    height: f32, // size along x
+    center: [f32; 3],
+    stretch_springs: bool,
+    bend_springs: bool,
+}
+
+impl ClothGridBuilder {
+    pub fn new(rows: u32, columns: u32) -> Self {
+        assert!(rows >= 2 && columns >= 2, "a cloth grid needs at least 2 rows and 2 columns");
+        ClothGridBuilder {
+            rows,
+            columns,
+            width: 1.0,
+            height: 1.0,
+            center: [0.0, 0.0, 0.0],
+            stretch_springs: true,
+            bend_springs: true,
+        }
+    }
+
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn center(mut self, center: [f32; 3]) -> Self {
+        self.center = center;
+        self
+    }
+
+    // structural and shear springs, not needed with the finite-element membrane
+    pub fn stretch_springs(mut self, stretch_springs: bool) -> Self {
+        self.stretch_springs = stretch_springs;
+        self
+    }
+
+    // bend springs, not needed with the dihedral bending
+    pub fn bend_springs(mut self, bend_springs: bool) -> Self {
+        self.bend_springs = bend_springs;
+        self
+    }
+
+    pub fn number_vertices(&self) -> u32 {
+        self.rows * self.columns
+    }
+
+    pub fn row_spacing(&self) -> f32 {
+        self.height / (self.rows - 1) as f32
+    }
+
+    pub fn column_spacing(&self) -> f32 {
+        self.width / (self.columns - 1) as f32
+    }
+
+    // the vertex of row i and column j is vertices[i * columns + j], the texture goes once over the whole cloth
+    pub fn vertices(&self) -> Vec<Vertex> {
+        let mut vertices = Vec::with_capacity(self.number_vertices() as usize);
+        for i in 0..self.rows {
+            for j in 0..self.columns {
+                vertices.push(Vertex {
+                    position: [
+                        self.center[0] + i as f32 * self.row_spacing() - (self.height / 2.0),
+                        self.center[1],
+                        self.center[2] + j as f32 * self.column_spacing() - (self.width / 2.0),
+                    ],
+                    normal: [0.0, 0.0, 0.0],
+                    tangent: [0.0, 0.0, 0.0],
+                    tex_coords: [ // au liieu d'utiliser des couleurs on utilises des points pour binder la texture qu'on va mettre
+                        i as f32 / (self.rows - 1) as f32, // correspond aux .png mais en relatif
+                        j as f32 / (self.columns - 1) as f32,
+                    ],
+                });
+            }
+        }
+        vertices
+    }
+
+    // two triangles per square of the grid
+    pub fn indices(&self) -> Vec<u32> {
+        let columns = self.columns;
+        let mut indices = Vec::with_capacity(6 * ((self.rows - 1) * (columns - 1)) as usize);
+        for i in 0..self.rows - 1 {
+            for j in 0..columns - 1 {
+                // first triangle
+                indices.push(i * columns + j);
+                indices.push(i * columns + j + 1);
+                indices.push((i + 1) * columns + j);
+                // second triangle
+                indices.push(i * columns + j + 1);
+                indices.push((i + 1) * columns + j + 1);
+                indices.push((i + 1) * columns + j);
+            }
+        }
+        indices
+    }
+
+    // the springs of every vertex to its neighbours, sorted by vertex: up to 4 structural, 4 shear and 4 bend springs,
+    // fewer on the borders. every spring is there twice, once from each of its vertices.
+    // vertex_materials gives the stiffness and damping, a spring between two materials takes the mean of both
+    pub fn springs(&self, vertex_materials: &[Material]) -> Vec<Spring> {
+        let row_spacing = self.row_spacing();
+        let column_spacing = self.column_spacing();
+        let shear_length = (row_spacing * row_spacing + column_spacing * column_spacing).sqrt();
+
+        // les voisins d'un sommet : (décalage de ligne, décalage de colonne, type de spring, direction dans le tissu, longueur au repos)
+        let neighbours = [
+            // structural springs
+            (0, -1, SPRING_STRUCTURAL, SpringKind::Weft, column_spacing),
+            (-1, 0, SPRING_STRUCTURAL, SpringKind::Warp, row_spacing),
+            (0, 1, SPRING_STRUCTURAL, SpringKind::Weft, column_spacing),
+            (1, 0, SPRING_STRUCTURAL, SpringKind::Warp, row_spacing),
+            // shear springs
+            (-1, -1, SPRING_SHEAR, SpringKind::Bias, shear_length),
+            (1, -1, SPRING_SHEAR, SpringKind::Bias, shear_length),
+            (1, 1, SPRING_SHEAR, SpringKind::Bias, shear_length),
+            (-1, 1, SPRING_SHEAR, SpringKind::Bias, shear_length),
+            // bend springs
+            (0, -2, SPRING_BEND, SpringKind::Bend, column_spacing * 2.0),
+            (-2, 0, SPRING_BEND, SpringKind::Bend, row_spacing * 2.0),
+            (0, 2, SPRING_BEND, SpringKind::Bend, column_spacing * 2.0),
+            (2, 0, SPRING_BEND, SpringKind::Bend, row_spacing * 2.0),
+        ];
+
+        let mut springs = Vec::new();
+        for inital_index in 0..self.number_vertices() {
+            let row = (inital_index / self.columns) as i32;
+            let col = (inital_index % self.columns) as i32;
+
+            for (row_offset, col_offset, spring_type, kind, rest_length) in neighbours {
+                if spring_type == SPRING_BEND && !self.bend_springs || spring_type != SPRING_BEND && !self.stretch_springs {
+                    continue;
+                }
+                let linked_row = row + row_offset;
+                let linked_col = col + col_offset;
+                // au bord du tissu il n'y a pas de voisin, on ne crée pas de spring
+                if linked_row < 0 || linked_row >= self.rows as i32 || linked_col < 0 || linked_col >= self.columns as i32 {
+                    continue;
+                }
+                let linked_index = (linked_row * self.columns as i32 + linked_col) as u32;
+
+                let (stiffness, damping) = material::spring_parameters(vertex_materials, inital_index as usize, linked_index as usize, kind);
+                springs.push(Spring {
+                    inital_index,
+                    linked_index,
+                    rest_length,
+                    stiffness,
+                    damping,
+                    spring_type,
+                });
+            }
+        }
+        springs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the properties are checked on every grid from 2x2 to MAX_SIZE x MAX_SIZE, square or not
+    const MAX_SIZE: u32 = 12;
+
+    fn grids() -> impl Iterator<Item = ClothGridBuilder> {
+        (2..=MAX_SIZE).flat_map(|rows| (2..=MAX_SIZE).map(move |columns| ClothGridBuilder::new(rows, columns).size(3.0 + columns as f32, 2.0 + rows as f32).center([1.0, 5.0, -2.0])))
+    }
+
+    fn materials(grid: &ClothGridBuilder) -> Vec<Material> {
+        // a different stiffness for each kind of spring, to check the kinds
+        let material = Material {
+            warp_stiffness: 1.0,
+            weft_stiffness: 2.0,
+            bias_stiffness: 3.0,
+            bend_stiffness: 4.0,
+            ..material::COTTON
+        };
+        vec![material; grid.number_vertices() as usize]
+    }
+
+    fn distance(vertices: &[Vertex], vertex_1: u32, vertex_2: u32) -> f32 {
+        let position_1 = vertices[vertex_1 as usize].position;
+        let position_2 = vertices[vertex_2 as usize].position;
+        (0..3).map(|axis| (position_1[axis] - position_2[axis]).powi(2)).sum::<f32>().sqrt()
+    }
+
+    #[test]
+    fn indices_make_two_triangles_per_square() {
+        for grid in grids() {
+            let indices = grid.indices();
+            assert_eq!(indices.len() as u32, 6 * (grid.rows - 1) * (grid.columns - 1));
+            assert!(indices.iter().all(|&index| index < grid.number_vertices()));
+            for triangle in indices.chunks_exact(3) {
+                assert!(triangle[0] != triangle[1] && triangle[1] != triangle[2] && triangle[0] != triangle[2]);
+            }
+        }
+    }
+
+    #[test]
+    fn vertices_cover_the_size_around_the_center() {
+        for grid in grids() {
+            let vertices = grid.vertices();
+            assert_eq!(vertices.len() as u32, grid.number_vertices());
+            let first = vertices[0].position;
+            let last = vertices[vertices.len() - 1].position;
+            assert!((first[0] - (grid.center[0] - grid.height / 2.0)).abs() < 1e-4 && (last[0] - (grid.center[0] + grid.height / 2.0)).abs() < 1e-4);
+            assert!((first[2] - (grid.center[2] - grid.width / 2.0)).abs() < 1e-4 && (last[2] - (grid.center[2] + grid.width / 2.0)).abs() < 1e-4);
+            assert!(vertices.iter().all(|vertex| vertex.position[1] == grid.center[1]));
+            assert_eq!(vertices[0].tex_coords, [0.0, 0.0]);
+            assert_eq!(vertices[vertices.len() - 1].tex_coords, [1.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn springs_are_symmetric() {
+        for grid in grids() {
+            let springs = grid.springs(&materials(&grid));
+            for spring in &springs {
+                let reverse = springs
+                    .iter()
+                    .filter(|other| other.inital_index == spring.linked_index && other.linked_index == spring.inital_index)
+                    .collect::<Vec<_>>();
+                assert_eq!(reverse.len(), 1, "{}x{}: the spring {} -> {} has {} reverse springs", grid.rows, grid.columns, spring.inital_index, spring.linked_index, reverse.len());
+                assert_eq!(reverse[0].spring_type, spring.spring_type);
+                assert_eq!(reverse[0].rest_length, spring.rest_length);
+                assert_eq!(reverse[0].stiffness, spring.stiffness);
+            }
+        }
+    }
+
+    #[test]
+    fn springs_are_sorted_by_vertex_and_in_range() {
+        for grid in grids() {
+            let springs = grid.springs(&materials(&grid));
+            assert!(springs.windows(2).all(|pair| pair[0].inital_index <= pair[1].inital_index));
+            for spring in &springs {
+                assert!(spring.inital_index < grid.number_vertices() && spring.linked_index < grid.number_vertices());
+                assert_ne!(spring.inital_index, spring.linked_index);
+            }
+        }
+    }
+
+    #[test]
+    fn springs_per_vertex_depend_on_the_borders() {
+        for grid in grids() {
+            let springs = grid.springs(&materials(&grid));
+            let (rows, columns) = (grid.rows as i32, grid.columns as i32);
+            for vertex in 0..grid.number_vertices() {
+                let (row, col) = ((vertex / grid.columns) as i32, (vertex % grid.columns) as i32);
+                let inside = |row_offset: i32, col_offset: i32| (0..rows).contains(&(row + row_offset)) && (0..columns).contains(&(col + col_offset));
+                let count = |offsets: &[(i32, i32)]| offsets.iter().filter(|&&(row_offset, col_offset)| inside(row_offset, col_offset)).count();
+                let expected = [
+                    count(&[(0, -1), (-1, 0), (0, 1), (1, 0)]),
+                    count(&[(-1, -1), (1, -1), (1, 1), (-1, 1)]),
+                    count(&[(0, -2), (-2, 0), (0, 2), (2, 0)]),
+                ];
+
+                for (spring_type, expected) in [SPRING_STRUCTURAL, SPRING_SHEAR, SPRING_BEND].into_iter().zip(expected) {
+                    let actual = springs.iter().filter(|spring| spring.inital_index == vertex && spring.spring_type == spring_type).count();
+                    assert_eq!(actual, expected, "{}x{}: vertex {} has {} springs of type {}", grid.rows, grid.columns, vertex, actual, spring_type);
+                }
+            }
+            // une grille d'au moins 5x5 a des sommets avec les 12 springs
+            if grid.rows >= 5 && grid.columns >= 5 {
+                let middle = (grid.rows / 2) * grid.columns + grid.columns / 2;
+                assert_eq!(springs.iter().filter(|spring| spring.inital_index == middle).count(), 12);
+            }
+        }
+    }
+
+    #[test]
+    fn rest_lengths_are_the_distances_in_the_grid() {
+        for grid in grids() {
+            let vertices = grid.vertices();
+            let springs = grid.springs(&materials(&grid));
+            let shear_length = (grid.row_spacing().powi(2) + grid.column_spacing().powi(2)).sqrt();
+            for spring in &springs {
+                let distance = distance(&vertices, spring.inital_index, spring.linked_index);
+                assert!((distance - spring.rest_length).abs() < 1e-4, "{}x{}: rest length {} for a distance of {}", grid.rows, grid.columns, spring.rest_length, distance);
+                if spring.spring_type == SPRING_SHEAR {
+                    assert_eq!(spring.rest_length, shear_length);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn springs_take_the_stiffness_of_their_direction() {
+        for grid in grids() {
+            let springs = grid.springs(&materials(&grid));
+            for spring in &springs {
+                let same_row = spring.inital_index / grid.columns == spring.linked_index / grid.columns;
+                let expected = match spring.spring_type {
+                    SPRING_STRUCTURAL if same_row => 2.0, // weft, along a row
+                    SPRING_STRUCTURAL => 1.0, // warp
+                    SPRING_SHEAR => 3.0,
+                    _ => 4.0,
+                };
+                assert_eq!(spring.stiffness, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn springs_can_be_left_out() {
+        for grid in grids() {
+            let materials = materials(&grid);
+            let without_bend = ClothGridBuilder { bend_springs: false, ..grid };
+            assert!(without_bend.springs(&materials).iter().all(|spring| spring.spring_type != SPRING_BEND));
+            let only_bend = ClothGridBuilder { stretch_springs: false, bend_springs: true, ..without_bend };
+            assert!(only_bend.springs(&materials).iter().all(|spring| spring.spring_type == SPRING_BEND));
+        }
+    }
+}
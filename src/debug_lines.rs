@@ -0,0 +1,159 @@
+use wgpu_bootstrap::wgpu;
+
+use crate::gpu::Gpu;
+use crate::spring_chunks::SpringChunk;
+
+// uniform of debug_vectors.wgsl, the same layout as its DebugSettings struct
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DebugSettings {
+    pub velocity_scale: f32, // a velocity is drawn as the distance it goes in velocity_scale seconds
+    pub normal_length: f32,
+}
+
+// the buffers of the simulation the overlay reads, in the vertex shaders
+pub struct DebugBuffers<'a> {
+    pub positions: [&'a wgpu::Buffer; 2], // the two states of the cloth, the overlay draws the one of the last step
+    pub velocities: [&'a wgpu::Buffer; 2],
+    pub springs: &'a wgpu::Buffer,
+    pub spring_chunks: &'a [SpringChunk], // the springs are drawn one chunk at a time, with the uniform of each chunk
+    pub spring_chunk_buffers: &'a [wgpu::Buffer],
+    pub membrane_triangles: &'a wgpu::Buffer,
+    pub vertex_elements: &'a wgpu::Buffer, // the spring offsets then the triangles of each vertex, as in forces_compute.wgsl
+}
+
+// lines drawn over the cloth: the springs coloured by type (structural red, shear green, bend blue),
+// and the velocity (orange) and normal (purple) of each vertex, each one turned on and off on its own
+pub struct DebugOverlay {
+    springs_pipeline: wgpu::RenderPipeline,
+    vectors_pipeline: wgpu::RenderPipeline,
+    bind_groups: Vec<[wgpu::BindGroup; 2]>, // per chunk of springs then per state
+    chunk_springs: Vec<std::ops::Range<u32>>, // the springs each chunk draws
+    number_vertices: u32,
+    pub show_springs: bool,
+    pub show_velocities: bool,
+    pub show_normals: bool,
+}
+
+impl DebugOverlay {
+    pub fn new(context: &impl Gpu, buffers: DebugBuffers, spring_offsets: &[u32], number_vertices: u32, settings: DebugSettings) -> Self {
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let uniform_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        // les deux shaders partagent le même layout, chacun n'utilise que ce dont il a besoin
+        let layout = context.device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Debug Bind Group Layout"),
+            entries: &[
+                storage_entry(0),
+                storage_entry(1),
+                storage_entry(2),
+                storage_entry(3),
+                storage_entry(4),
+                uniform_entry(5),
+                uniform_entry(6),
+            ],
+        });
+
+        let settings_buffer = context.create_buffer(&[settings], wgpu::BufferUsages::UNIFORM);
+        let bind_groups = buffers.spring_chunks.iter().zip(buffers.spring_chunk_buffers).map(|(chunk, chunk_buffer)| [0, 1].map(|state| context.create_bind_group(
+            "Debug Bind Group",
+            &layout,
+            &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffers.positions[state].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: buffers.velocities[state].as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: chunk.springs_binding(buffers.springs),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: buffers.membrane_triangles.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: buffers.vertex_elements.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: settings_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: chunk_buffer.as_entire_binding(),
+                },
+            ],
+        ))).collect();
+        let chunk_springs = buffers.spring_chunks.iter().map(|chunk| chunk.springs(spring_offsets)).collect();
+
+        let springs_pipeline = context.create_render_pipeline(
+            "Pipeline Debug Springs",
+            include_str!("debug_springs.wgsl"),
+            &[],
+            &[context.camera_bind_group_layout(), &layout],
+            wgpu::PrimitiveTopology::LineList,
+        );
+        let vectors_pipeline = context.create_render_pipeline(
+            "Pipeline Debug Vectors",
+            include_str!("debug_vectors.wgsl"),
+            &[],
+            &[context.camera_bind_group_layout(), &layout],
+            wgpu::PrimitiveTopology::LineList,
+        );
+
+        DebugOverlay {
+            springs_pipeline,
+            vectors_pipeline,
+            bind_groups,
+            chunk_springs,
+            number_vertices,
+            show_springs: false,
+            show_velocities: false,
+            show_normals: false,
+        }
+    }
+
+    // state: the buffers of the last step, MyApp::front
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, camera_bind_group: &'a wgpu::BindGroup, state: usize) {
+        if self.show_springs {
+            render_pass.set_pipeline(&self.springs_pipeline);
+            render_pass.set_bind_group(0, camera_bind_group, &[]);
+            for (bind_groups, springs) in self.bind_groups.iter().zip(&self.chunk_springs) {
+                render_pass.set_bind_group(1, &bind_groups[state], &[]);
+                render_pass.draw(2 * springs.start..2 * springs.end, 0..1);
+            }
+        }
+
+        // instance 0 for the velocities, 1 for the normals
+        let first_instance = if self.show_velocities { 0 } else { 1 };
+        let last_instance = if self.show_normals { 2 } else { 1 };
+        if first_instance < last_instance {
+            render_pass.set_pipeline(&self.vectors_pipeline);
+            render_pass.set_bind_group(0, camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.bind_groups[0][state], &[]); // the vectors don't read the springs, any chunk does
+            render_pass.draw(0..2 * self.number_vertices, first_instance..last_instance);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// debug overlay: every spring as a line, read directly from the springs buffer.
+// two vertices per spring in the buffer, vertex_index / 2 is the spring and vertex_index % 2 its end. drawn once per chunk of springs
+
+struct CameraUniform {
+    view: mat4x4<f32>,
+    proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> matrices: CameraUniform;
+
+struct Spring {
+    vertex_index_1: u32,
+    vertex_index_2: u32,
+    rest_length: f32,
+    stiffness: f32,
+    damping: f32,
+    spring_type: u32,
+}
+
+@group(1) @binding(0) var<storage, read> verticiesPositions: array<vec4<f32>>;
+@group(1) @binding(2) var<storage, read> springsR: array<Spring>; // the springs of one chunk, see spring_chunks.rs
+
+struct SpringChunk {
+    first_vertex: u32,
+    end_vertex: u32,
+    first_spring: u32, // springsR[0]
+    end_spring: u32,
+}
+@group(1) @binding(6) var<uniform> chunk: SpringChunk;
+
+// spring types, the same as in main.rs
+let SPRING_STRUCTURAL: u32 = 0u;
+let SPRING_SHEAR: u32 = 1u;
+let SPRING_BEND: u32 = 2u;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let spring = springsR[index / 2u - chunk.first_spring];
+    var vertex_index = spring.vertex_index_1;
+    if index % 2u == 1u {
+        vertex_index = spring.vertex_index_2;
+    }
+    let position = verticiesPositions[vertex_index].xyz;
+
+    var out: VertexOutput;
+    out.clip_position = matrices.proj * matrices.view * vec4<f32>(position, 1.0);
+    if spring.spring_type == SPRING_STRUCTURAL {
+        out.color = vec3<f32>(0.9, 0.1, 0.1);
+    } else if spring.spring_type == SPRING_SHEAR {
+        out.color = vec3<f32>(0.1, 0.7, 0.1);
+    } else if spring.spring_type == SPRING_BEND {
+        out.color = vec3<f32>(0.1, 0.2, 0.9);
+    }
+
+    // each spring is in the list of both of its vertices, only one of the two is drawn. torn springs aren't drawn either:
+    // the line is moved out of the screen
+    if spring.vertex_index_1 > spring.vertex_index_2 || spring.spring_type > SPRING_BEND {
+        out.clip_position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+    }
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
@@ -0,0 +1,86 @@
+// debug overlay: the velocity and the normal of each vertex as lines starting at the vertex.
+// instance 0 draws the velocities and instance 1 the normals, two vertices per line
+
+struct CameraUniform {
+    view: mat4x4<f32>,
+    proj: mat4x4<f32>,
+};
+@group(0) @binding(0)
+var<uniform> matrices: CameraUniform;
+
+struct MembraneTriangle {
+    vertex_0: u32,
+    vertex_1: u32,
+    vertex_2: u32,
+    rest_inverse_00: f32,
+    rest_inverse_01: f32,
+    rest_inverse_10: f32,
+    rest_inverse_11: f32,
+    rest_area: f32,
+    lame_mu: f32,
+    lame_lambda: f32,
+    damping: f32,
+}
+
+// the same as DebugSettings in debug_lines.rs
+struct DebugSettings {
+    velocity_scale: f32,
+    normal_length: f32,
+}
+
+@group(1) @binding(0) var<storage, read> verticiesPositions: array<vec4<f32>>;
+@group(1) @binding(1) var<storage, read> verticiesVelocities: array<vec4<f32>>;
+@group(1) @binding(3) var<storage, read> membraneTriangles: array<MembraneTriangle>;
+@group(1) @binding(4) var<storage, read> vertexElements: array<u32>; // packed like in forces_compute.wgsl, the triangles start at N + 1
+@group(1) @binding(5) var<uniform> settings: DebugSettings;
+
+fn vertex_position(index: u32) -> vec3<f32> {
+    return verticiesPositions[index].xyz;
+}
+
+// the normals of the vertex buffer aren't updated by the simulation, so it is the sum of the normals of the triangles around the vertex,
+// each one weighted by the area of the triangle
+fn vertex_normal(index: u32) -> vec3<f32> {
+    var normal = vec3<f32>(0.0, 0.0, 0.0);
+    let triangles_start = arrayLength(&verticiesPositions) + 1u;
+    for (var i = vertexElements[triangles_start + index]; i < vertexElements[triangles_start + index + 1u]; i++) {
+        let element = membraneTriangles[vertexElements[i] / 4u];
+        let position_0 = vertex_position(element.vertex_0);
+        normal += cross(vertex_position(element.vertex_1) - position_0, vertex_position(element.vertex_2) - position_0);
+    }
+    if length(normal) > 0.0 {
+        normal = normalize(normal);
+    }
+    return normal;
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, @builtin(instance_index) instance: u32) -> VertexOutput {
+    let vertex = index / 2u;
+    var position = vertex_position(vertex);
+
+    var out: VertexOutput;
+    if instance == 0u {
+        if index % 2u == 1u {
+            position += settings.velocity_scale * verticiesVelocities[vertex].xyz;
+        }
+        out.color = vec3<f32>(0.9, 0.6, 0.0);
+    } else {
+        if index % 2u == 1u {
+            position += settings.normal_length * vertex_normal(vertex);
+        }
+        out.color = vec3<f32>(0.7, 0.0, 0.7);
+    }
+    out.clip_position = matrices.proj * matrices.view * vec4<f32>(position, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
@@ -0,0 +1,95 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// energies and momentum of the cloth after a step, summed on the GPU by diagnostics.wgsl (same layout as its Diagnostics struct)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Diagnostics {
+    pub kinetic_energy: f32,
+    pub gravitational_energy: f32, // 0 at y = 0
+    pub structural_energy: f32, // elastic energy of the springs, by type
+    pub shear_energy: f32,
+    pub bend_energy: f32,
+    pub momentum: [f32; 3],
+    pub membrane_energy: f32, // the finite-element triangles, 0 without MEMBRANE_FEM
+    pub hinge_energy: f32, // the dihedral bending, 0 without DIHEDRAL_BENDING
+}
+
+impl Diagnostics {
+    // add the partial sums of the workgroups
+    pub fn sum(partials: &[Diagnostics]) -> Diagnostics {
+        partials.iter().fold(Diagnostics::default(), |total, partial| Diagnostics {
+            kinetic_energy: total.kinetic_energy + partial.kinetic_energy,
+            gravitational_energy: total.gravitational_energy + partial.gravitational_energy,
+            structural_energy: total.structural_energy + partial.structural_energy,
+            shear_energy: total.shear_energy + partial.shear_energy,
+            bend_energy: total.bend_energy + partial.bend_energy,
+            momentum: [
+                total.momentum[0] + partial.momentum[0],
+                total.momentum[1] + partial.momentum[1],
+                total.momentum[2] + partial.momentum[2],
+            ],
+            membrane_energy: total.membrane_energy + partial.membrane_energy,
+            hinge_energy: total.hinge_energy + partial.hinge_energy,
+        })
+    }
+
+    pub fn elastic_energy(&self) -> f32 {
+        self.structural_energy + self.shear_energy + self.bend_energy + self.membrane_energy + self.hinge_energy
+    }
+
+    // what should stay constant without damping, collisions and grab, its drift shows what the integrator adds or loses
+    pub fn total_energy(&self) -> f32 {
+        self.kinetic_energy + self.gravitational_energy + self.elastic_energy()
+    }
+}
+
+// one line per step in a CSV file, to plot the energy drift of a solver configuration
+pub struct DiagnosticsLog {
+    writer: BufWriter<File>,
+}
+
+impl DiagnosticsLog {
+    pub fn create(path: &str) -> io::Result<Self> {
+        if let Some(directory) = Path::new(path).parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "step,time,kinetic,gravitational,structural,shear,bend,membrane,hinge,total,momentum_x,momentum_y,momentum_z")?;
+        Ok(DiagnosticsLog { writer })
+    }
+
+    pub fn write(&mut self, step: u32, time: f32, diagnostics: &Diagnostics) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            step,
+            time,
+            diagnostics.kinetic_energy,
+            diagnostics.gravitational_energy,
+            diagnostics.structural_energy,
+            diagnostics.shear_energy,
+            diagnostics.bend_energy,
+            diagnostics.membrane_energy,
+            diagnostics.hinge_energy,
+            diagnostics.total_energy(),
+            diagnostics.momentum[0],
+            diagnostics.momentum[1],
+            diagnostics.momentum[2],
+        )
+    }
+}
+
+// "--diagnostics <path>" writes the energies and the momentum of every step to a CSV file
+pub const OPTIONS: &[(&str, bool)] = &[("--diagnostics", true)];
+
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Option<DiagnosticsLog> {
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        if arg == "--diagnostics" {
+            path = args.next();
+        }
+    }
+    path.map(|path| DiagnosticsLog::create(&path).expect("could not create the diagnostics file"))
+}
@@ -0,0 +1,226 @@
+struct Spring {
+    vertex_index_1: u32,
+    vertex_index_2: u32,
+    rest_length: f32,
+    stiffness: f32,
+    damping: f32,
+    spring_type: u32,
+}
+
+struct Hinge {
+    vertex_0: u32,
+    vertex_1: u32,
+    vertex_2: u32,
+    vertex_3: u32,
+    rest_angle: f32,
+    stiffness: f32,
+    damping: f32,
+}
+
+struct MembraneTriangle {
+    vertex_0: u32,
+    vertex_1: u32,
+    vertex_2: u32,
+    rest_inverse_00: f32,
+    rest_inverse_01: f32,
+    rest_inverse_10: f32,
+    rest_inverse_11: f32,
+    rest_area: f32,
+    lame_mu: f32,
+    lame_lambda: f32,
+    damping: f32,
+}
+
+struct ComputeData {
+    delta_time: f32,
+    nb_vertices: u32,
+    sphere_radius: f32,
+    sphere_center_x: f32,
+    sphere_center_y: f32,
+    sphere_center_z: f32,
+    vertex_mass: f32,
+    grab_index: i32,
+    grab_target_x: f32,
+    grab_target_y: f32,
+    grab_target_z: f32,
+    grab_stiffness: f32,
+    tear_ratio: f32,
+    plastic_yield: f32,
+    plastic_rate: f32,
+    dihedral_bending: u32,
+    membrane_fem: u32,
+    strain_limit: f32,
+}
+// what one workgroup adds up, the CPU adds the few partial sums together
+struct Diagnostics {
+    kinetic_energy: f32,
+    gravitational_energy: f32,
+    structural_energy: f32,
+    shear_energy: f32,
+    bend_energy: f32,
+    momentum_x: f32,
+    momentum_y: f32,
+    momentum_z: f32,
+    membrane_energy: f32, // the finite-element triangles, with MEMBRANE_FEM
+    hinge_energy: f32, // the dihedral bending, with DIHEDRAL_BENDING
+}
+
+// spring types, the same as in main.rs
+let SPRING_STRUCTURAL: u32 = 0u;
+let SPRING_SHEAR: u32 = 1u;
+let SPRING_BEND: u32 = 2u;
+
+let GRAVITY: f32 = 9.81; // the same as forces_compute.wgsl, the potential energy is 0 at y = 0
+let PI: f32 = 3.14159265;
+let WORKGROUP_SIZE: u32 = 128u;
+
+// the state of the last step, only read: it is the start of the next step, bound at group 0 as in the step
+@group(0) @binding(0) var<storage, read> verticiesPositions: array<vec4<f32>>;
+@group(0) @binding(1) var<storage, read> verticiesVelocities: array<vec4<f32>>;
+@group(1) @binding(0) var<uniform> data: ComputeData;
+@group(2) @binding(0) var<storage, read> springsR: array<Spring>;
+// the springs, triangles and hinges of each vertex, the same buffer as in forces_compute.wgsl
+@group(2) @binding(1) var<storage, read> vertexElements: array<u32>;
+@group(2) @binding(2) var<storage, read_write> partials: array<Diagnostics>; // une somme par workgroup
+@group(2) @binding(4) var<storage, read> hinges: array<Hinge>;
+@group(2) @binding(5) var<storage, read> membraneTriangles: array<MembraneTriangle>;
+
+// the vertices of this dispatch and the springs bound in springsR, see spring_chunks.rs
+struct SpringChunk {
+    first_vertex: u32,
+    end_vertex: u32,
+    first_spring: u32, // springsR[0]
+    end_spring: u32,
+}
+@group(2) @binding(3) var<uniform> chunk: SpringChunk;
+
+// (kinetic, gravitational, structural, shear), (bend, momentum) and (membrane, hinge) of each thread, added two by two
+var<workgroup> energies: array<vec4<f32>, 128>;
+var<workgroup> bend_momentum: array<vec4<f32>, 128>;
+var<workgroup> element_energies: array<vec2<f32>, 128>;
+
+fn vertex_position(index: u32) -> vec3<f32> {
+    return verticiesPositions[index].xyz;
+}
+
+// St. Venant-Kirchhoff energy of a triangle, area * (mu E:E + lambda / 2 tr(E)^2), the stress of membrane_force in forces_compute.wgsl derives from it
+fn membrane_energy(element: MembraneTriangle) -> f32 {
+    let x0 = vertex_position(element.vertex_0);
+    let deformed = mat2x3<f32>(vertex_position(element.vertex_1) - x0, vertex_position(element.vertex_2) - x0);
+    let rest_inverse = mat2x2<f32>(element.rest_inverse_00, element.rest_inverse_01, element.rest_inverse_10, element.rest_inverse_11);
+    let deformation = deformed * rest_inverse;
+    let strain = 0.5 * (transpose(deformation) * deformation - mat2x2<f32>(1.0, 0.0, 0.0, 1.0));
+
+    let trace = strain[0][0] + strain[1][1];
+    let strain_squared = dot(strain[0], strain[0]) + dot(strain[1], strain[1]);
+    return element.rest_area * (element.lame_mu * strain_squared + 0.5 * element.lame_lambda * trace * trace);
+}
+
+// energy of a hinge, stiffness * scale * angle_error^2 / 2 for the force -stiffness * scale * angle_error * gradient of hinge_force
+fn hinge_energy(hinge: Hinge) -> f32 {
+    let x0 = vertex_position(hinge.vertex_0);
+    let edge = vertex_position(hinge.vertex_1) - x0;
+    let edge_length = length(edge);
+    let normal_a = cross(edge, vertex_position(hinge.vertex_2) - x0);
+    let normal_b = cross(vertex_position(hinge.vertex_3) - x0, edge);
+    let normal_a_length = length(normal_a);
+    let normal_b_length = length(normal_b);
+    if edge_length == 0.0 || normal_a_length == 0.0 || normal_b_length == 0.0 {
+        return 0.0; // triangle écrasé, comme hinge_force
+    }
+
+    let unit_a = normal_a / normal_a_length;
+    let unit_b = normal_b / normal_b_length;
+    let angle = atan2(dot(cross(unit_a, unit_b), edge / edge_length), dot(unit_a, unit_b));
+    var angle_error = angle - hinge.rest_angle;
+    if angle_error > PI {
+        angle_error -= 2.0 * PI;
+    } else if angle_error < -PI {
+        angle_error += 2.0 * PI;
+    }
+    let scale = edge_length * edge_length / (normal_a_length + normal_b_length);
+    return 0.5 * hinge.stiffness * scale * angle_error * angle_error;
+}
+
+// energies and momentum of the cloth: each thread measures its vertex and its springs, then the workgroup sums them in shared memory.
+// no early return here, every thread of the workgroup has to reach the barriers
+@compute @workgroup_size(128, 1, 1)
+fn main(@builtin(global_invocation_id) invocation: vec3<u32>, @builtin(local_invocation_id) local: vec3<u32>, @builtin(workgroup_id) group: vec3<u32>) {
+    let param = invocation + vec3<u32>(chunk.first_vertex, 0u, 0u);
+    var energy = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+    var bend_and_momentum = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+    var element_energy = vec2<f32>(0.0, 0.0);
+
+    if (param.x < chunk.end_vertex) {
+        let position = vertex_position(param.x);
+        let velocity = verticiesVelocities[param.x].xyz;
+        energy.x = 0.5 * data.vertex_mass * dot(velocity, velocity);
+        energy.y = data.vertex_mass * GRAVITY * position.y;
+        bend_and_momentum = vec4<f32>(0.0, data.vertex_mass * velocity);
+
+        // every spring is in the list of both of its vertices, so each one only counts half of its energy
+        for (var spring_index = vertexElements[param.x]; spring_index < vertexElements[param.x + 1u]; spring_index++) {
+            let spring = springsR[spring_index - chunk.first_spring];
+            let stretch = length(position - vertex_position(spring.vertex_index_2)) - spring.rest_length;
+            let spring_energy = 0.25 * spring.stiffness * stretch * stretch;
+            if spring.spring_type == SPRING_STRUCTURAL {
+                energy.z += spring_energy;
+            } else if spring.spring_type == SPRING_SHEAR {
+                energy.w += spring_energy;
+            } else if spring.spring_type == SPRING_BEND {
+                bend_and_momentum.x += spring_energy;
+            }
+            // torn springs no longer store anything
+        }
+
+        // a triangle or a hinge is in the list of each of its vertices, only its first vertex (role 0) counts it
+        if data.membrane_fem != 0u {
+            let triangles_start = data.nb_vertices + 1u;
+            for (var i = vertexElements[triangles_start + param.x]; i < vertexElements[triangles_start + param.x + 1u]; i++) {
+                let triangle_reference = vertexElements[i];
+                if triangle_reference % 4u == 0u {
+                    element_energy.x += membrane_energy(membraneTriangles[triangle_reference / 4u]);
+                }
+            }
+        }
+        if data.dihedral_bending != 0u {
+            let hinges_start = vertexElements[2u * data.nb_vertices + 1u];
+            for (var i = vertexElements[hinges_start + param.x]; i < vertexElements[hinges_start + param.x + 1u]; i++) {
+                let hinge_reference = vertexElements[i];
+                if hinge_reference % 4u == 0u {
+                    element_energy.y += hinge_energy(hinges[hinge_reference / 4u]);
+                }
+            }
+        }
+    }
+
+    energies[local.x] = energy;
+    bend_momentum[local.x] = bend_and_momentum;
+    element_energies[local.x] = element_energy;
+
+    for (var stride = WORKGROUP_SIZE / 2u; stride > 0u; stride = stride / 2u) {
+        workgroupBarrier();
+        if local.x < stride {
+            energies[local.x] += energies[local.x + stride];
+            bend_momentum[local.x] += bend_momentum[local.x + stride];
+            element_energies[local.x] += element_energies[local.x + stride];
+        }
+    }
+
+    if local.x == 0u {
+        let total_energy = energies[0];
+        let total_bend_momentum = bend_momentum[0];
+        partials[group.x + chunk.first_vertex / 128u] = Diagnostics( // the chunks start on a whole workgroup
+            total_energy.x,
+            total_energy.y,
+            total_energy.z,
+            total_energy.w,
+            total_bend_momentum.x,
+            total_bend_momentum.y,
+            total_bend_momentum.z,
+            total_bend_momentum.w,
+            element_energies[0].x,
+            element_energies[0].y,
+        );
+    }
+}
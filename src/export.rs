@@ -0,0 +1,168 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use wgpu_bootstrap::{
+    cgmath::{self, InnerSpace},
+    default::Vertex,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ExportFormat {
+    Obj,
+    Ply,
+}
+
+// writes the cloth to one file per exported frame. the pattern is a path where a run of # is replaced by the frame number,
+// padded with zeros to the number of #: "export/cloth_####.obj" gives export/cloth_0001.obj, export/cloth_0002.obj...
+// the extension of the pattern chooses between OBJ and PLY
+pub struct FrameExporter {
+    pattern: String,
+    format: ExportFormat,
+    pub interval: u32, // number of simulation steps between two exported frames
+    next_frame: u32,
+}
+
+impl FrameExporter {
+    pub fn new(pattern: &str, interval: u32) -> Self {
+        let format = match Path::new(pattern).extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("ply") => ExportFormat::Ply,
+            _ => ExportFormat::Obj,
+        };
+        Self {
+            pattern: pattern.to_string(),
+            format,
+            interval: interval.max(1),
+            next_frame: 1,
+        }
+    }
+
+    // write the next frame and return its path
+    pub fn write_frame(&mut self, vertices: &[Vertex], indices: &[u32]) -> io::Result<PathBuf> {
+        let path = frame_path(&self.pattern, self.next_frame, "obj");
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        let mut file = BufWriter::new(File::create(&path)?);
+        let triangles = visible_triangles(indices);
+        let normals = vertex_normals(vertices, &triangles);
+        match self.format {
+            ExportFormat::Obj => write_obj(&mut file, vertices, &normals, &triangles)?,
+            ExportFormat::Ply => write_ply(&mut file, vertices, &normals, &triangles)?,
+        }
+        file.flush()?;
+
+        self.next_frame += 1;
+        Ok(path)
+    }
+}
+
+// path of a frame: the run of # in the pattern becomes the frame number padded with zeros,
+// a pattern without # gets the number before its extension
+pub fn frame_path(pattern: &str, frame: u32, default_extension: &str) -> PathBuf {
+    match pattern.find('#') {
+        Some(start) => {
+            let width = pattern[start..].chars().take_while(|&c| c == '#').count();
+            let number = format!("{:0width$}", frame, width = width);
+            PathBuf::from(format!("{}{}{}", &pattern[..start], number, &pattern[start + width..]))
+        }
+        None => {
+            let path = Path::new(pattern);
+            let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("cloth");
+            let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or(default_extension);
+            path.with_file_name(format!("{}_{:04}.{}", stem, frame, extension))
+        }
+    }
+}
+
+// "--export <pattern>" turns the export on from the start, "--export-every <steps>" changes the interval
+pub const OPTIONS: &[(&str, bool)] = &[("--export", true), ("--export-every", true)];
+
+pub fn parse_args(mut args: impl Iterator<Item = String>, default_interval: u32) -> Option<FrameExporter> {
+    let mut pattern = None;
+    let mut interval = default_interval;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--export" => pattern = args.next(),
+            "--export-every" => {
+                interval = args
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .expect("--export-every needs a number of steps");
+            }
+            _ => {}
+        }
+    }
+
+    pattern.map(|pattern| FrameExporter::new(&pattern, interval))
+}
+
+// the triangles torn away are collapsed to a single point in the index buffer, they are not exported
+pub fn visible_triangles(indices: &[u32]) -> Vec<[u32; 3]> {
+    indices
+        .chunks_exact(3)
+        .filter(|triangle| !(triangle[0] == triangle[1] && triangle[1] == triangle[2]))
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .collect()
+}
+
+// the GPU only moves the positions, the normals are computed again from the faces (weighted by their area)
+pub fn vertex_normals(vertices: &[Vertex], triangles: &[[u32; 3]]) -> Vec<cgmath::Vector3<f32>> {
+    let mut normals = vec![cgmath::Vector3::new(0.0, 0.0, 0.0); vertices.len()];
+    for triangle in triangles {
+        let [x0, x1, x2] = triangle.map(|index| cgmath::Vector3::from(vertices[index as usize].position));
+        let normal = (x1 - x0).cross(x2 - x0);
+        for &index in triangle {
+            normals[index as usize] += normal;
+        }
+    }
+    normals
+        .into_iter()
+        .map(|normal| if normal.magnitude2() > 0.0 { normal.normalize() } else { cgmath::Vector3::unit_y() })
+        .collect()
+}
+
+fn write_obj(file: &mut impl Write, vertices: &[Vertex], normals: &[cgmath::Vector3<f32>], triangles: &[[u32; 3]]) -> io::Result<()> {
+    for vertex in vertices {
+        writeln!(file, "v {} {} {}", vertex.position[0], vertex.position[1], vertex.position[2])?;
+    }
+    for vertex in vertices {
+        writeln!(file, "vt {} {}", vertex.tex_coords[0], 1.0 - vertex.tex_coords[1])?; // en OBJ v monte
+    }
+    for normal in normals {
+        writeln!(file, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+    }
+    for triangle in triangles {
+        let [a, b, c] = triangle.map(|index| index + 1); // les indices OBJ commencent à 1
+        writeln!(file, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+    }
+    Ok(())
+}
+
+fn write_ply(file: &mut impl Write, vertices: &[Vertex], normals: &[cgmath::Vector3<f32>], triangles: &[[u32; 3]]) -> io::Result<()> {
+    writeln!(file, "ply")?;
+    writeln!(file, "format ascii 1.0")?;
+    writeln!(file, "element vertex {}", vertices.len())?;
+    for property in ["x", "y", "z", "nx", "ny", "nz", "s", "t"] {
+        writeln!(file, "property float {}", property)?;
+    }
+    writeln!(file, "element face {}", triangles.len())?;
+    writeln!(file, "property list uchar uint vertex_indices")?;
+    writeln!(file, "end_header")?;
+
+    for (vertex, normal) in vertices.iter().zip(normals) {
+        writeln!(
+            file,
+            "{} {} {} {} {} {} {} {}",
+            vertex.position[0], vertex.position[1], vertex.position[2],
+            normal.x, normal.y, normal.z,
+            vertex.tex_coords[0], 1.0 - vertex.tex_coords[1],
+        )?;
+    }
+    for triangle in triangles {
+        writeln!(file, "3 {} {} {}", triangle[0], triangle[1], triangle[2])?;
+    }
+    Ok(())
+}
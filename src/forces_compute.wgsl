@@ -1,110 +1,258 @@
-struct Position {
-    position_x: f32,
-    position_y: f32,
-    position_z: f32,
-    normal_x: f32,
-    normal_y: f32,
-    normal_z: f32,
-    tangent_x: f32,
-    tangent_y: f32,
-    tangent_z: f32,
-    tex_coords_x: f32,
-    tex_coords_y: f32,
-}
-
-struct Velocity {
-    velocity_x: f32,
-    velocity_y: f32,
-    velocity_z: f32,
-}
-
 struct ComputeData {
     delta_time: f32,
-    nb_vertices: f32,
+    nb_vertices: u32,
     sphere_radius: f32,
     sphere_center_x: f32,
     sphere_center_y: f32,
     sphere_center_z: f32,
     vertex_mass: f32,
-    structural_stiffness: f32,
-    shear_stiffness: f32,
-    bend_stiffness: f32,
-    structural_damping: f32,
-    shear_damping: f32,
-    bend_damping: f32,
+    grab_index: i32,
+    grab_target_x: f32,
+    grab_target_y: f32,
+    grab_target_z: f32,
+    grab_stiffness: f32,
+    tear_ratio: f32,
+    plastic_yield: f32,
+    plastic_rate: f32,
+    dihedral_bending: u32,
+    membrane_fem: u32,
+    strain_limit: f32,
 }
 
 struct Spring {
-    vertex_index_1: f32,
-    vertex_index_2: f32,
+    vertex_index_1: u32,
+    vertex_index_2: u32,
     rest_length: f32,
+    stiffness: f32,
+    damping: f32,
+    spring_type: u32,
 }
 
-@group(0) @binding(0) var<storage, read_write> verticiesPositions: array<Position>;
-@group(1) @binding(0) var<storage, read_write> verticiesVelocities: array<Velocity>;
+struct Hinge {
+    vertex_0: u32,
+    vertex_1: u32,
+    vertex_2: u32,
+    vertex_3: u32,
+    rest_angle: f32,
+    stiffness: f32,
+    damping: f32,
+}
+
+struct MembraneTriangle {
+    vertex_0: u32,
+    vertex_1: u32,
+    vertex_2: u32,
+    rest_inverse_00: f32,
+    rest_inverse_01: f32,
+    rest_inverse_10: f32,
+    rest_inverse_11: f32,
+    rest_area: f32,
+    lame_mu: f32,
+    lame_lambda: f32,
+    damping: f32,
+}
+
+// spring types, the same as in main.rs
+let SPRING_BEND: u32 = 2u;
+let SPRING_TORN: u32 = 3u;
+
+let PI: f32 = 3.14159265;
+
+// the forces only read the state of the start of the step (group 0) and write the new velocities in the other copy (group 1),
+// so a vertex never sees the new velocity of a neighbour whatever the order of the threads. the positions are written by compute.wgsl
+@group(0) @binding(0) var<storage, read> startPositions: array<vec4<f32>>;
+@group(0) @binding(1) var<storage, read> startVelocities: array<vec4<f32>>;
+@group(1) @binding(1) var<storage, read_write> verticiesVelocities: array<vec4<f32>>;
 @group(2) @binding(0) var<uniform> data: ComputeData;
-@group(3) @binding(0) var<storage, read> springsR: array<Spring>;
+@group(3) @binding(0) var<storage, read_write> springsR: array<Spring>;
+// les springs du sommet i vont de vertexElements[i] à vertexElements[i + 1] dans springsR, puis les triangles de chaque sommet à partir de N + 1
+// (offsets dans ce même buffer, puis index du triangle * 4 + rôle du sommet dans le triangle) et ses hinges au même format à partir de vertexElements[2N + 1]
+// (tout dans le même buffer car un shader n'a droit qu'à 8 storage buffers)
+@group(3) @binding(1) var<storage, read> vertexElements: array<u32>;
+@group(3) @binding(2) var<storage, read> hinges: array<Hinge>;
+@group(3) @binding(3) var<storage, read> membraneTriangles: array<MembraneTriangle>;
+
+// the vertices of this dispatch and the springs bound in springsR, see spring_chunks.rs
+struct SpringChunk {
+    first_vertex: u32,
+    end_vertex: u32,
+    first_spring: u32, // springsR[0]
+    end_spring: u32,
+}
+@group(3) @binding(4) var<uniform> chunk: SpringChunk;
+
+fn vertex_position(index: u32) -> vec3<f32> {
+    return startPositions[index].xyz;
+}
+
+fn vertex_velocity(index: u32) -> vec3<f32> {
+    return startVelocities[index].xyz;
+}
+
+// bending force of a hinge on one of its four vertices (role 0 and 1 on the shared edge, 2 and 3 opposite to it)
+// the force pulls the angle between the two triangles back to its rest angle (Bridson et al. 2003)
+fn hinge_force(hinge: Hinge, role: u32) -> vec3<f32> {
+    let x0 = vertex_position(hinge.vertex_0);
+    let x1 = vertex_position(hinge.vertex_1);
+    let x2 = vertex_position(hinge.vertex_2);
+    let x3 = vertex_position(hinge.vertex_3);
+
+    let edge = x1 - x0;
+    let edge_length = length(edge);
+    let normal_a = cross(edge, x2 - x0);
+    let normal_b = cross(x3 - x0, edge);
+    let normal_a_length2 = dot(normal_a, normal_a);
+    let normal_b_length2 = dot(normal_b, normal_b);
+    if edge_length == 0.0 || normal_a_length2 == 0.0 || normal_b_length2 == 0.0 {
+        return vec3<f32>(0.0, 0.0, 0.0); // triangle écrasé, l'angle n'est pas défini
+    }
+
+    let unit_a = normal_a / sqrt(normal_a_length2);
+    let unit_b = normal_b / sqrt(normal_b_length2);
+    let angle = atan2(dot(cross(unit_a, unit_b), edge / edge_length), dot(unit_a, unit_b));
+    var angle_error = angle - hinge.rest_angle;
+    if angle_error > PI {
+        angle_error -= 2.0 * PI;
+    } else if angle_error < -PI {
+        angle_error += 2.0 * PI;
+    }
+
+    // derivative of the angle with respect to the position of each vertex
+    let gradient_a = normal_a / normal_a_length2;
+    let gradient_b = normal_b / normal_b_length2;
+    var gradients: array<vec3<f32>, 4>;
+    gradients[0] = -(gradient_a * dot(x2 - x1, edge) + gradient_b * dot(x3 - x1, edge)) / edge_length;
+    gradients[1] = (gradient_a * dot(x2 - x0, edge) + gradient_b * dot(x3 - x0, edge)) / edge_length;
+    gradients[2] = -gradient_a * edge_length;
+    gradients[3] = -gradient_b * edge_length;
+
+    let angle_velocity = dot(gradients[0], vertex_velocity(hinge.vertex_0))
+        + dot(gradients[1], vertex_velocity(hinge.vertex_1))
+        + dot(gradients[2], vertex_velocity(hinge.vertex_2))
+        + dot(gradients[3], vertex_velocity(hinge.vertex_3));
+
+    // the stiffness is scaled so the bending doesn't depend on the size of the triangles
+    let scale = edge_length * edge_length / (sqrt(normal_a_length2) + sqrt(normal_b_length2));
+    return -(hinge.stiffness * scale * angle_error + hinge.damping * angle_velocity) * gradients[role];
+}
+
+// St. Venant-Kirchhoff membrane force of a triangle element on one of its three vertices.
+// F = Ds * Dm^-1 maps the rest shape to the current one, the Green strain E = (F^T F - I) / 2 gives the second Piola-Kirchhoff stress
+// S = 2 mu E + lambda tr(E) I, plus a viscous stress on the strain rate, and the forces are -area * F * S * Dm^-T
+fn membrane_force(element: MembraneTriangle, role: u32) -> vec3<f32> {
+    let x0 = vertex_position(element.vertex_0);
+    let v0 = vertex_velocity(element.vertex_0);
+    let deformed = mat2x3<f32>(vertex_position(element.vertex_1) - x0, vertex_position(element.vertex_2) - x0);
+    let deformed_velocity = mat2x3<f32>(vertex_velocity(element.vertex_1) - v0, vertex_velocity(element.vertex_2) - v0);
+    let rest_inverse = mat2x2<f32>(element.rest_inverse_00, element.rest_inverse_01, element.rest_inverse_10, element.rest_inverse_11);
+
+    let deformation = deformed * rest_inverse;
+    let deformation_rate = deformed_velocity * rest_inverse;
+    let identity = mat2x2<f32>(1.0, 0.0, 0.0, 1.0);
+
+    let strain = 0.5 * (transpose(deformation) * deformation - identity);
+    let strain_rate = 0.5 * (transpose(deformation_rate) * deformation + transpose(deformation) * deformation_rate);
+    let stress = 2.0 * element.lame_mu * strain + element.lame_lambda * (strain[0][0] + strain[1][1]) * identity
+        + 2.0 * element.damping * strain_rate;
+
+    let forces = -element.rest_area * (deformation * stress) * transpose(rest_inverse);
+    if role == 1u {
+        return forces[0];
+    } else if role == 2u {
+        return forces[1];
+    }
+    return -(forces[0] + forces[1]);
+}
 
 @compute @workgroup_size(128, 1, 1)
-fn main(@builtin(global_invocation_id) param: vec3<u32>) {
-    if (param.x >= u32(data.nb_vertices)) {
+fn main(@builtin(global_invocation_id) invocation: vec3<u32>) {
+    let param = invocation + vec3<u32>(chunk.first_vertex, 0u, 0u);
+    if (param.x >= chunk.end_vertex) {
           return;
     }
 
     var force_sum = vec3<f32>(0.0, 0.0, 0.0);
-    for (var i = 0 ; i < 12; i++) {
-        let spring = springsR[param.x*u32(12) + u32(i)];
-        let vertex_index_1 = u32(spring.vertex_index_1);
-        let vertex_index_2 = u32(spring.vertex_index_2);
-        let rest_length = spring.rest_length;
+    for (var spring_index = vertexElements[param.x]; spring_index < vertexElements[param.x + 1u]; spring_index++) {
+        let spring = springsR[spring_index - chunk.first_spring];
+        let vertex_index_1 = spring.vertex_index_1;
+        let vertex_index_2 = spring.vertex_index_2;
+        var rest_length = spring.rest_length;
 
-        if u32(spring.vertex_index_2) <= u32(data.nb_vertices) {
+        if spring.spring_type != SPRING_TORN {
             // calculate the distance between the two vertices
-            let position_1 = vec3<f32>(verticiesPositions[vertex_index_1].position_x, verticiesPositions[vertex_index_1].position_y, verticiesPositions[vertex_index_1].position_z); // position du point fixé
-            let position_2 = vec3<f32>(verticiesPositions[vertex_index_2].position_x, verticiesPositions[vertex_index_2].position_y, verticiesPositions[vertex_index_2].position_z); // positioin du point lié au points fixé
+            let position_1 = vertex_position(vertex_index_1); // position du point fixé
+            let position_2 = vertex_position(vertex_index_2); // positioin du point lié au points fixé
 
             var distance = length(position_1 - position_2);
             var direction = normalize(position_1 - position_2);
+
+            // structural and shear springs tear when they are stretched too much, the CPU then removes the triangles around them
+            if data.tear_ratio > 0.0 && spring.spring_type != SPRING_BEND && distance > data.tear_ratio * rest_length {
+                springsR[spring_index - chunk.first_spring].spring_type = SPRING_TORN;
+                continue;
+            }
+
+            // plasticity: when the strain goes over the yield, the rest length creeps toward the current length and stays there
+            if data.plastic_yield > 0.0 && abs(distance - rest_length) > data.plastic_yield * rest_length {
+                rest_length += data.plastic_rate * data.delta_time * (distance - rest_length);
+                springsR[spring_index - chunk.first_spring].rest_length = rest_length;
+            }
  
             // calculate the speed of the first vertex relative to the second
-            let velocity_1 = vec3<f32>(verticiesVelocities[vertex_index_1].velocity_x, verticiesVelocities[vertex_index_1].velocity_y, verticiesVelocities[vertex_index_1].velocity_z);
-            let velocity_2 = vec3<f32>(verticiesVelocities[vertex_index_2].velocity_x, verticiesVelocities[vertex_index_2].velocity_y, verticiesVelocities[vertex_index_2].velocity_z);
+            let velocity_1 = vertex_velocity(vertex_index_1);
+            let velocity_2 = vertex_velocity(vertex_index_2);
             let relative_velocity = length(velocity_1 - velocity_2);
             let velocity_direction = normalize(velocity_1 - velocity_2);
             
 
-            if i < 4 {
-                let force = -data.structural_stiffness * (distance - rest_length);
-                force_sum += force * direction;
-                if relative_velocity != 0.0 {
-                    let damping_force = -data.structural_damping * relative_velocity;
-                    force_sum += damping_force * velocity_direction;
-                }
-            } else if i < 8 {
-                let force = -data.shear_stiffness * (distance - rest_length);
+            // stiffness and damping come from the material of the spring (warp, weft, bias or bend)
+            if spring.spring_type != SPRING_BEND {
+                let force = -spring.stiffness * (distance - rest_length);
                 force_sum += force * direction;
                 if relative_velocity != 0.0 {
-                    let damping_force = -data.shear_damping * relative_velocity;
+                    let damping_force = -spring.damping * relative_velocity;
                     force_sum += damping_force * velocity_direction;
                 }
-            } else if i < 12 {
+            } else {
                 // direction.z = 0.0;
-                let force = -data.bend_stiffness * (distance - rest_length) - data.bend_damping * relative_velocity;
+                let force = -spring.stiffness * (distance - rest_length) - spring.damping * relative_velocity;
                 force_sum += force * direction;
                 if relative_velocity != 0.0 {
-                    let damping_force = -data.bend_damping * relative_velocity;
+                    let damping_force = -spring.damping * relative_velocity;
                     force_sum += damping_force * velocity_direction;
                 }
             }
         }
     }
+
+    // dihedral bending: each vertex goes through the hinges it belongs to and only keeps its own part of the force
+    if data.dihedral_bending != 0u {
+        let hinges_start = vertexElements[2u * data.nb_vertices + 1u];
+        for (var i = vertexElements[hinges_start + param.x]; i < vertexElements[hinges_start + param.x + 1u]; i++) {
+            let hinge_reference = vertexElements[i];
+            force_sum += hinge_force(hinges[hinge_reference / 4u], hinge_reference % 4u);
+        }
+    }
+
+    // finite-element membrane, gathered the same way as the hinges
+    if data.membrane_fem != 0u {
+        let triangles_start = data.nb_vertices + 1u;
+        for (var i = vertexElements[triangles_start + param.x]; i < vertexElements[triangles_start + param.x + 1u]; i++) {
+            let triangle_reference = vertexElements[i];
+            force_sum += membrane_force(membraneTriangles[triangle_reference / 4u], triangle_reference % 4u);
+        }
+    }
+
     force_sum.y += -9.81 * data.vertex_mass;
 
-    // update the velocity of the vertex
-    verticiesVelocities[param.x].velocity_x += (force_sum.x / data.vertex_mass) * data.delta_time;
-    verticiesVelocities[param.x].velocity_y += (force_sum.y / data.vertex_mass) * data.delta_time;
-    verticiesVelocities[param.x].velocity_z += (force_sum.z / data.vertex_mass) * data.delta_time;
+    // the vertex held with the mouse is pulled toward the cursor
+    if data.grab_index >= 0 && u32(data.grab_index) == param.x {
+        let position = vertex_position(param.x);
+        let grab_target = vec3<f32>(data.grab_target_x, data.grab_target_y, data.grab_target_z);
+        force_sum += data.grab_stiffness * (grab_target - position);
+    }
 
-    // update the position of the vertex, or it crashes
-    verticiesPositions[param.x].position_x += 0.0;
+    // update the velocity of the vertex
+    let velocity = vertex_velocity(param.x) + (force_sum / data.vertex_mass) * data.delta_time;
+    verticiesVelocities[param.x] = vec4<f32>(velocity, 0.0);
 }
\ No newline at end of file
@@ -0,0 +1,267 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use wgpu_bootstrap::default::Vertex;
+
+use crate::export;
+
+// records the cloth every few steps and writes it as a single glTF binary (.glb):
+// the cloth mesh with its texture, the sphere collider, and the animation as one morph target per recorded frame.
+// the weights are animated so that only the target of the current frame is on, with a linear blend between two frames.
+// every frame is a full copy of the positions and of the normals, and the weights grow with the square of the number of frames, keep the recordings short
+pub struct GlbRecorder {
+    path: PathBuf,
+    pub interval: u32, // number of simulation steps between two recorded frames
+    max_frames: u32, // the file is written when this number of frames is reached
+    base_vertices: Vec<Vertex>, // first frame, the morph targets are relative to it
+    frames: Vec<Vec<[f32; 3]>>, // positions of the next frames
+    times: Vec<f32>, // simulation time of every frame, from 0
+    start_time: f32,
+}
+
+// what goes in the file beside the animated cloth
+pub struct Scene<'a> {
+    pub cloth_indices: &'a [u32],
+    pub cloth_texture_png: &'a [u8],
+    pub sphere_vertices: &'a [Vertex],
+    pub sphere_indices: &'a [u16],
+}
+
+impl GlbRecorder {
+    pub fn new(path: &str, interval: u32, max_frames: u32) -> Self {
+        Self {
+            path: PathBuf::from(path),
+            interval: interval.max(1),
+            max_frames: max_frames.max(2),
+            base_vertices: Vec::new(),
+            frames: Vec::new(),
+            times: Vec::new(),
+            start_time: 0.0,
+        }
+    }
+
+    // add a frame, returns true when the recording is full and should be written
+    pub fn record(&mut self, vertices: &[Vertex], time: f32) -> bool {
+        if self.base_vertices.is_empty() {
+            self.base_vertices = vertices.to_vec();
+            self.start_time = time;
+        } else {
+            self.frames.push(vertices.iter().map(|vertex| vertex.position).collect());
+        }
+        self.times.push(time - self.start_time);
+        self.times.len() as u32 >= self.max_frames
+    }
+
+    pub fn write(&self, scene: &Scene) -> io::Result<PathBuf> {
+        if let Some(directory) = self.path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)?;
+        }
+        if self.times.len() < 2 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "the animation needs at least two frames"));
+        }
+        let mut builder = GlbBuilder::default();
+
+        // cloth
+        let visible_triangles = export::visible_triangles(scene.cloth_indices);
+        let triangles: Vec<u32> = visible_triangles.iter().flatten().copied().collect();
+        let normals: Vec<[f32; 3]> = export::vertex_normals(&self.base_vertices, &visible_triangles).into_iter().map(|normal| normal.into()).collect();
+        let base_positions: Vec<[f32; 3]> = self.base_vertices.iter().map(|vertex| vertex.position).collect();
+        let cloth_positions = builder.vec3_accessor(&base_positions);
+        let cloth_normals = builder.accessor(bytemuck::cast_slice(&normals), normals.len(), "VEC3", FLOAT, 34962);
+        let cloth_tex_coords = builder.accessor(bytemuck::cast_slice(&self.base_vertices.iter().map(|vertex| vertex.tex_coords).collect::<Vec<_>>()), self.base_vertices.len(), "VEC2", FLOAT, 34962);
+        let cloth_indices = builder.accessor(bytemuck::cast_slice(&triangles), triangles.len(), "SCALAR", UNSIGNED_INT, 34963);
+
+        let targets: Vec<Value> = self
+            .frames
+            .iter()
+            .map(|frame| {
+                let offsets: Vec<[f32; 3]> = frame.iter().zip(&base_positions).map(|(position, base)| difference(position, base)).collect();
+                // the normals of the frame with the triangles of the base mesh, or the cloth would stay lit as in the first frame
+                let frame_vertices: Vec<Vertex> = self.base_vertices.iter().zip(frame).map(|(vertex, &position)| Vertex { position, ..*vertex }).collect();
+                let normal_offsets: Vec<[f32; 3]> = export::vertex_normals(&frame_vertices, &visible_triangles)
+                    .into_iter()
+                    .zip(&normals)
+                    .map(|(normal, base)| difference(&normal.into(), base))
+                    .collect();
+                json!({ "POSITION": builder.vec3_accessor(&offsets), "NORMAL": builder.accessor(bytemuck::cast_slice(&normal_offsets), normal_offsets.len(), "VEC3", FLOAT, 34962) })
+            })
+            .collect();
+
+        // texture
+        let image_view = builder.buffer_view(scene.cloth_texture_png, None);
+
+        // sphere collider
+        let sphere_positions: Vec<[f32; 3]> = scene.sphere_vertices.iter().map(|vertex| vertex.position).collect();
+        let sphere_positions = builder.vec3_accessor(&sphere_positions);
+        let sphere_indices = builder.accessor(bytemuck::cast_slice(scene.sphere_indices), scene.sphere_indices.len(), "SCALAR", UNSIGNED_SHORT, 34963);
+
+        // animation: at the time of frame i only the weight of target i - 1 is 1, the first frame is the base mesh
+        let number_targets = self.frames.len();
+        let mut weights = vec![0.0f32; self.times.len() * number_targets];
+        for frame in 1..self.times.len() {
+            weights[frame * number_targets + frame - 1] = 1.0;
+        }
+        let times = builder.accessor(bytemuck::cast_slice(&self.times), self.times.len(), "SCALAR", FLOAT, 0);
+        builder.accessors[times]["min"] = json!([self.times[0]]);
+        builder.accessors[times]["max"] = json!([self.times[self.times.len() - 1]]);
+        let weights = builder.accessor(bytemuck::cast_slice(&weights), weights.len(), "SCALAR", FLOAT, 0);
+
+        let gltf = json!({
+            "asset": { "version": "2.0", "generator": "cloth_simulation" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0, 1] }],
+            "nodes": [
+                { "name": "Cloth", "mesh": 0 },
+                { "name": "Sphere Collider", "mesh": 1 },
+            ],
+            "meshes": [
+                {
+                    "name": "Cloth",
+                    "primitives": [{
+                        "attributes": { "POSITION": cloth_positions, "NORMAL": cloth_normals, "TEXCOORD_0": cloth_tex_coords },
+                        "indices": cloth_indices,
+                        "material": 0,
+                        "targets": targets,
+                    }],
+                    "weights": vec![0.0; number_targets],
+                },
+                {
+                    "name": "Sphere Collider",
+                    "primitives": [{
+                        "attributes": { "POSITION": sphere_positions },
+                        "indices": sphere_indices,
+                        "material": 1,
+                    }],
+                },
+            ],
+            "materials": [
+                {
+                    "name": "Cloth",
+                    "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 }, "metallicFactor": 0.0, "roughnessFactor": 1.0 },
+                    "doubleSided": true,
+                },
+                {
+                    "name": "Collider",
+                    "pbrMetallicRoughness": { "baseColorFactor": [0.2, 0.3, 0.9, 1.0], "metallicFactor": 0.0, "roughnessFactor": 1.0 },
+                },
+            ],
+            "textures": [{ "source": 0, "sampler": 0 }],
+            "samplers": [{ "magFilter": 9729, "minFilter": 9987, "wrapS": 10497, "wrapT": 10497 }],
+            "images": [{ "bufferView": image_view, "mimeType": "image/png" }],
+            "animations": [{
+                "name": "Simulation",
+                "samplers": [{ "input": times, "output": weights, "interpolation": "LINEAR" }],
+                "channels": [{ "sampler": 0, "target": { "node": 0, "path": "weights" } }],
+            }],
+            "buffers": [{ "byteLength": builder.binary.len() }],
+            "bufferViews": builder.buffer_views,
+            "accessors": builder.accessors,
+        });
+
+        write_glb(&self.path, &gltf, &builder.binary)?;
+        Ok(self.path.clone())
+    }
+}
+
+// "--glb <path>" records from the start, "--glb-every <steps>" and "--glb-frames <frames>" change the interval and the length
+pub const OPTIONS: &[(&str, bool)] = &[("--glb", true), ("--glb-every", true), ("--glb-frames", true)];
+
+pub fn parse_args(mut args: impl Iterator<Item = String>, default_interval: u32, default_frames: u32) -> Option<GlbRecorder> {
+    let mut path = None;
+    let mut interval = default_interval;
+    let mut frames = default_frames;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--glb" => path = args.next(),
+            "--glb-every" => interval = args.next().and_then(|value| value.parse().ok()).expect("--glb-every needs a number of steps"),
+            "--glb-frames" => frames = args.next().and_then(|value| value.parse().ok()).expect("--glb-frames needs a number of frames"),
+            _ => {}
+        }
+    }
+
+    path.map(|path| GlbRecorder::new(&path, interval, frames))
+}
+
+fn difference(value: &[f32; 3], base: &[f32; 3]) -> [f32; 3] {
+    [value[0] - base[0], value[1] - base[1], value[2] - base[2]]
+}
+
+// component types of the accessors
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const UNSIGNED_SHORT: u32 = 5123;
+
+// the binary chunk and the views and accessors that point in it
+#[derive(Default)]
+struct GlbBuilder {
+    binary: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl GlbBuilder {
+    // append the bytes, aligned on 4 bytes as the accessors need, and return the index of their view
+    fn buffer_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        while !self.binary.len().is_multiple_of(4) {
+            self.binary.push(0);
+        }
+        let mut view = json!({ "buffer": 0, "byteOffset": self.binary.len(), "byteLength": bytes.len() });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.binary.extend_from_slice(bytes);
+        self.buffer_views.push(view);
+        self.buffer_views.len() - 1
+    }
+
+    // target is 34962 for vertex data, 34963 for indices and 0 for animation data
+    fn accessor(&mut self, bytes: &[u8], count: usize, kind: &str, component_type: u32, target: u32) -> usize {
+        let view = self.buffer_view(bytes, (target != 0).then_some(target));
+        self.accessors.push(json!({ "bufferView": view, "componentType": component_type, "count": count, "type": kind }));
+        self.accessors.len() - 1
+    }
+
+    // positions need their bounds in glTF
+    fn vec3_accessor(&mut self, values: &[[f32; 3]]) -> usize {
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for value in values {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(value[axis]);
+                max[axis] = max[axis].max(value[axis]);
+            }
+        }
+        let accessor = self.accessor(bytemuck::cast_slice(values), values.len(), "VEC3", FLOAT, 34962);
+        self.accessors[accessor]["min"] = json!(min);
+        self.accessors[accessor]["max"] = json!(max);
+        accessor
+    }
+}
+
+// header, JSON chunk padded with spaces and binary chunk padded with zeros
+fn write_glb(path: &Path, gltf: &Value, binary: &[u8]) -> io::Result<()> {
+    let mut json = serde_json::to_vec(gltf)?;
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+    let mut binary = binary.to_vec();
+    while !binary.len().is_multiple_of(4) {
+        binary.push(0);
+    }
+
+    let total_length = 12 + 8 + json.len() + 8 + binary.len();
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(b"glTF")?;
+    file.write_all(&2u32.to_le_bytes())?;
+    file.write_all(&(total_length as u32).to_le_bytes())?;
+    file.write_all(&(json.len() as u32).to_le_bytes())?;
+    file.write_all(b"JSON")?;
+    file.write_all(&json)?;
+    file.write_all(&(binary.len() as u32).to_le_bytes())?;
+    file.write_all(b"BIN\0")?;
+    file.write_all(&binary)?;
+    file.flush()
+}
@@ -0,0 +1,152 @@
+use wgpu_bootstrap::{
+    camera::Camera,
+    context::Context,
+    texture::create_texture_bind_group,
+    wgpu,
+};
+
+// what the simulation needs from the GPU. the window context of wgpu-bootstrap implements it,
+// and so does the offscreen context of the headless mode (headless.rs) which has no window to get a Context from
+pub trait Gpu {
+    fn device(&self) -> &wgpu::Device;
+    fn queue(&self) -> &wgpu::Queue;
+    fn size(&self) -> (u32, u32); // size of the rendered image in pixels
+    fn aspect_ratio(&self) -> f32;
+    fn color_format(&self) -> wgpu::TextureFormat; // format of the image the render pipelines draw in
+    fn camera_bind_group_layout(&self) -> &wgpu::BindGroupLayout;
+    fn texture_bind_group_layout(&self) -> &wgpu::BindGroupLayout;
+
+    fn create_buffer<T: bytemuck::Pod>(&self, data: &[T], usage: wgpu::BufferUsages) -> wgpu::Buffer;
+    fn update_buffer<T: bytemuck::Pod>(&self, buffer: &wgpu::Buffer, data: &[T]);
+    fn create_bind_group(&self, label: &str, layout: &wgpu::BindGroupLayout, entries: &[wgpu::BindGroupEntry]) -> wgpu::BindGroup;
+    fn create_compute_pipeline(&self, label: &str, source: &str) -> wgpu::ComputePipeline;
+    // the same with bind group layouts written by hand: the bind groups of the cloth state are shared by every compute pass,
+    // and the layouts wgpu derives from each shader only match when every shader binds them the same way
+    fn create_compute_pipeline_with_layouts(&self, label: &str, source: &str, bind_group_layouts: &[&wgpu::BindGroupLayout]) -> wgpu::ComputePipeline {
+        let module = self.device().create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let layout = self.device().create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+        self.device().create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(label),
+            layout: Some(&layout),
+            module: &module,
+            entry_point: "main",
+        })
+    }
+    fn create_bind_group_layout(&self, label: &str, entries: &[wgpu::BindGroupLayoutEntry]) -> wgpu::BindGroupLayout {
+        self.device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries,
+        })
+    }
+    fn create_render_pipeline(
+        &self,
+        label: &str,
+        source: &str,
+        buffers: &[wgpu::VertexBufferLayout],
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        topology: wgpu::PrimitiveTopology,
+    ) -> wgpu::RenderPipeline;
+    fn create_camera_bind_group(&self, camera: &Camera) -> (wgpu::Buffer, wgpu::BindGroup);
+    fn create_texture_bind_group(&self, label: &str, bytes: &[u8]) -> wgpu::BindGroup;
+}
+
+impl Gpu for Context {
+    fn device(&self) -> &wgpu::Device {
+        Context::device(self)
+    }
+
+    fn queue(&self) -> &wgpu::Queue {
+        Context::queue(self)
+    }
+
+    fn size(&self) -> (u32, u32) {
+        let size = self.get_size();
+        (size.width, size.height)
+    }
+
+    fn aspect_ratio(&self) -> f32 {
+        self.get_aspect_ratio()
+    }
+
+    fn color_format(&self) -> wgpu::TextureFormat {
+        self.get_surface_format()
+    }
+
+    fn camera_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.camera_bind_group_layout
+    }
+
+    fn texture_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.texture_bind_group_layout
+    }
+
+    fn create_buffer<T: bytemuck::Pod>(&self, data: &[T], usage: wgpu::BufferUsages) -> wgpu::Buffer {
+        Context::create_buffer(self, data, usage)
+    }
+
+    fn update_buffer<T: bytemuck::Pod>(&self, buffer: &wgpu::Buffer, data: &[T]) {
+        Context::update_buffer(self, buffer, data)
+    }
+
+    fn create_bind_group(&self, label: &str, layout: &wgpu::BindGroupLayout, entries: &[wgpu::BindGroupEntry]) -> wgpu::BindGroup {
+        Context::create_bind_group(self, label, layout, entries)
+    }
+
+    fn create_compute_pipeline(&self, label: &str, source: &str) -> wgpu::ComputePipeline {
+        Context::create_compute_pipeline(self, label, source)
+    }
+
+    fn create_render_pipeline(
+        &self,
+        label: &str,
+        source: &str,
+        buffers: &[wgpu::VertexBufferLayout],
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        topology: wgpu::PrimitiveTopology,
+    ) -> wgpu::RenderPipeline {
+        Context::create_render_pipeline(self, label, source, buffers, bind_group_layouts, topology)
+    }
+
+    fn create_camera_bind_group(&self, camera: &Camera) -> (wgpu::Buffer, wgpu::BindGroup) {
+        camera.create_camera_bind_group(self)
+    }
+
+    fn create_texture_bind_group(&self, label: &str, bytes: &[u8]) -> wgpu::BindGroup {
+        let texture = self.create_texture(label, bytes);
+        create_texture_bind_group(self, &texture)
+    }
+}
+
+// entries of the bind group layouts of the compute shaders
+pub fn storage_entry(binding: u32, read_only: bool) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+pub fn uniform_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
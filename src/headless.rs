@@ -0,0 +1,360 @@
+use wgpu_bootstrap::{
+    camera::Camera,
+    cgmath,
+    wgpu::{self, util::DeviceExt},
+};
+
+use crate::export;
+use crate::gpu::Gpu;
+use crate::offscreen::{OffscreenTarget, DEPTH_FORMAT};
+use crate::MyApp;
+
+const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+// cgmath builds OpenGL projections (z between -1 and 1), wgpu wants z between 0 and 1
+#[rustfmt::skip]
+const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+// the same matrices as the CameraUniform of cloth.wgsl and blue.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CameraUniform {
+    view: [[f32; 4]; 4],
+    proj: [[f32; 4]; 4],
+}
+
+pub struct HeadlessOptions {
+    pub pattern: String, // numbered like the OBJ export: "frames/cloth_####.png"
+    pub frames: u32,
+    pub width: u32,
+    pub height: u32,
+    pub fps: f32,
+    pub substeps: u32, // simulation steps per rendered frame
+    pub software: bool, // ask for the fallback (CPU) adapter, for machines without a GPU
+}
+
+// "--headless <pattern>" renders without a window, "--frames", "--width", "--height", "--fps", "--substeps" and "--software" set it up
+pub const OPTIONS: &[(&str, bool)] = &[("--headless", true), ("--frames", true), ("--width", true), ("--height", true), ("--fps", true), ("--substeps", true), ("--software", false)];
+
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Option<HeadlessOptions> {
+    let mut pattern = None;
+    let mut options = HeadlessOptions {
+        pattern: String::new(),
+        frames: 120,
+        width: 800,
+        height: 600,
+        fps: 30.0,
+        substeps: 2,
+        software: false,
+    };
+
+    while let Some(arg) = args.next() {
+        // a number of frames, pixels or steps, 0 would give an empty image or a simulation that doesn't move
+        let mut count = |name: &str| match args.next().and_then(|value| value.parse::<u32>().ok()) {
+            Some(count) if count > 0 => count,
+            _ => panic!("{} needs a whole number above 0", name),
+        };
+        match arg.as_str() {
+            "--headless" => pattern = args.next(),
+            "--frames" => options.frames = count("--frames"),
+            "--width" => options.width = count("--width"),
+            "--height" => options.height = count("--height"),
+            // 0 would give an infinite step
+            "--fps" => options.fps = match args.next().and_then(|value| value.parse::<f32>().ok()) {
+                Some(fps) if fps > 0.0 && fps.is_finite() => fps,
+                _ => panic!("--fps needs a number above 0"),
+            },
+            "--substeps" => options.substeps = count("--substeps"),
+            "--software" => options.software = true,
+            _ => {}
+        }
+    }
+
+    pattern.map(|pattern| HeadlessOptions { pattern, ..options })
+}
+
+// a GPU context without window or surface, the images are rendered in a texture and read back
+pub struct HeadlessContext {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    target: OffscreenTarget,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl HeadlessContext {
+    pub fn new(width: u32, height: u32, software: bool) -> Result<Self, String> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            force_fallback_adapter: software,
+            compatible_surface: None,
+        }))
+        .ok_or("no GPU adapter found, try --software")?;
+        eprintln!("rendering with {:?}", adapter.get_info()); // not on stdout, --hash and --bench print their results there
+
+        // the compute shaders bind up to 8 storage buffers, more than the downlevel defaults, so we take what the adapter offers
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("Headless Device"),
+                features: wgpu::Features::empty(),
+                limits: adapter.limits(),
+            },
+            None,
+        ))
+        .map_err(|error| error.to_string())?;
+
+        let target = OffscreenTarget::new(&device, width, height, COLOR_FORMAT);
+
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texture Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            target,
+            camera_bind_group_layout,
+            texture_bind_group_layout,
+        })
+    }
+
+    // draw the app and return its pixels (RGBA, 4 bytes per pixel, rows from the top)
+    pub fn render(&self, app: &MyApp) -> Vec<u8> {
+        self.target.render(&self.device, &self.queue, app)
+    }
+}
+
+impl Gpu for HeadlessContext {
+    fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.target.width, self.target.height)
+    }
+
+    fn aspect_ratio(&self) -> f32 {
+        self.target.width as f32 / self.target.height as f32
+    }
+
+    fn color_format(&self) -> wgpu::TextureFormat {
+        COLOR_FORMAT
+    }
+
+    fn camera_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.camera_bind_group_layout
+    }
+
+    fn texture_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.texture_bind_group_layout
+    }
+
+    fn create_buffer<T: bytemuck::Pod>(&self, data: &[T], usage: wgpu::BufferUsages) -> wgpu::Buffer {
+        self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(data),
+            usage: usage | wgpu::BufferUsages::COPY_DST, // comme wgpu-bootstrap, pour que update_buffer marche sur tous les buffers
+        })
+    }
+
+    fn update_buffer<T: bytemuck::Pod>(&self, buffer: &wgpu::Buffer, data: &[T]) {
+        self.queue.write_buffer(buffer, 0, bytemuck::cast_slice(data));
+    }
+
+    fn create_bind_group(&self, label: &str, layout: &wgpu::BindGroupLayout, entries: &[wgpu::BindGroupEntry]) -> wgpu::BindGroup {
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(label),
+            layout,
+            entries,
+        })
+    }
+
+    fn create_compute_pipeline(&self, label: &str, source: &str) -> wgpu::ComputePipeline {
+        let module = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        self.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(label),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+        })
+    }
+
+    fn create_render_pipeline(
+        &self,
+        label: &str,
+        source: &str,
+        buffers: &[wgpu::VertexBufferLayout],
+        bind_group_layouts: &[&wgpu::BindGroupLayout],
+        topology: wgpu::PrimitiveTopology,
+    ) -> wgpu::RenderPipeline {
+        let module = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let layout = self.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+        self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: COLOR_FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    }
+
+    fn create_camera_bind_group(&self, camera: &Camera) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let view = cgmath::Matrix4::look_at_rh(camera.eye, camera.target, camera.up);
+        let proj = OPENGL_TO_WGPU_MATRIX * cgmath::perspective(cgmath::Deg(camera.fovy), camera.aspect, camera.znear, camera.zfar);
+        let uniform = CameraUniform {
+            view: view.into(),
+            proj: proj.into(),
+        };
+
+        let buffer = self.create_buffer(&[uniform], wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST);
+        let bind_group = self.create_bind_group(
+            "Camera Bind Group",
+            &self.camera_bind_group_layout,
+            &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        );
+        (buffer, bind_group)
+    }
+
+    fn create_texture_bind_group(&self, label: &str, bytes: &[u8]) -> wgpu::BindGroup {
+        let image = image::load_from_memory(bytes).expect("could not decode the texture").to_rgba8();
+        let (width, height) = image.dimensions();
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+
+        let texture = self.device.create_texture_with_data(
+            &self.queue,
+            &wgpu::TextureDescriptor {
+                label: Some(label),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            },
+            &image,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        self.create_bind_group(
+            label,
+            &self.texture_bind_group_layout,
+            &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        )
+    }
+}
+
+// simulate and write one PNG per frame, at a fixed time step so two runs give the same images
+// args: the whole command line, for the options of the app (--heatmap, the exports...)
+pub fn run(options: &HeadlessOptions, args: &[String]) -> Result<(), String> {
+    let context = HeadlessContext::new(options.width, options.height, options.software)?;
+    let mut app = MyApp::new(&context, args);
+    let delta_time = 1.0 / (options.fps * options.substeps as f32);
+
+    for frame in 1..=options.frames {
+        for _ in 0..options.substeps {
+            app.step(&context, delta_time);
+        }
+        let pixels = context.render(&app);
+
+        let path = export::frame_path(&options.pattern, frame, "png");
+        if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            std::fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+        }
+        image::save_buffer(&path, &pixels, options.width, options.height, image::ColorType::Rgba8).map_err(|error| error.to_string())?;
+    }
+
+    println!("{} frames written to {}", options.frames, options.pattern);
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use wgpu_bootstrap::wgpu;
+
+pub const MAX_RAMP_COLORS: usize = 8;
+
+// how cloth.wgsl colours the cloth
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderMode {
+    Texture,
+    Strain, // heat map of the strain of each vertex, computed by strain.wgsl
+}
+
+// uniform of cloth.wgsl and legend.wgsl (group 2), the same layout as their RenderSettings struct
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct RenderSettings {
+    pub mode: u32, // 0 texture, 1 strain heat map
+    pub max_strain: f32, // strain shown with the last colour of the ramp, above it the cloth stays that colour
+    pub ramp_size: u32,
+    pub legend_tick: f32, // a line is drawn on the legend every legend_tick of strain
+    pub ramp: [[f32; 4]; MAX_RAMP_COLORS], // colours evenly spaced between a strain of 0 and max_strain, the 4th component is unused
+}
+
+impl RenderSettings {
+    pub fn new(mode: RenderMode, max_strain: f32, ramp: &[[f32; 3]], legend_tick: f32) -> Self {
+        assert!((2..=MAX_RAMP_COLORS).contains(&ramp.len()), "the colour ramp needs between 2 and {} colours", MAX_RAMP_COLORS);
+        let mut colors = [[0.0; 4]; MAX_RAMP_COLORS];
+        for (color, ramp_color) in colors.iter_mut().zip(ramp) {
+            *color = [ramp_color[0], ramp_color[1], ramp_color[2], 1.0];
+        }
+
+        RenderSettings {
+            mode: match mode {
+                RenderMode::Texture => 0,
+                RenderMode::Strain => 1,
+            },
+            max_strain,
+            ramp_size: ramp.len() as u32,
+            legend_tick,
+            ramp: colors,
+        }
+    }
+}
+
+pub fn create_settings_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Render Settings Bind Group Layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+// the strain of each vertex, a second vertex buffer of the cloth next to the Vertex of wgpu-bootstrap
+pub fn strain_buffer_layout<'a>() -> wgpu::VertexBufferLayout<'a> {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![4 => Float32];
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<f32>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &ATTRIBUTES,
+    }
+}
@@ -0,0 +1,58 @@
+// legend of the strain heat map: a bar on the right of the screen with the colour ramp, 0 at the bottom and max_strain at the top,
+// with a dark line every legend_tick of strain
+
+struct RenderSettings {
+    mode: u32,
+    max_strain: f32,
+    ramp_size: u32,
+    legend_tick: f32,
+    ramp: array<vec4<f32>, 8>,
+};
+@group(0) @binding(0)
+var<uniform> settings: RenderSettings;
+
+// position of the bar on the screen, between -1 and 1
+let LEGEND_LEFT: f32 = 0.85;
+let LEGEND_RIGHT: f32 = 0.92;
+let LEGEND_BOTTOM: f32 = -0.7;
+let LEGEND_TOP: f32 = 0.7;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) strain: f32,
+}
+
+// two triangles, no vertex buffer
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[index];
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(mix(LEGEND_LEFT, LEGEND_RIGHT, corner.x), mix(LEGEND_BOTTOM, LEGEND_TOP, corner.y), 0.0, 1.0);
+    out.strain = corner.y * settings.max_strain;
+    return out;
+}
+
+// the same as in cloth.wgsl
+fn ramp_color(strain: f32) -> vec4<f32> {
+    let position = clamp(strain / settings.max_strain, 0.0, 1.0) * f32(settings.ramp_size - 1u);
+    let index = min(u32(position), settings.ramp_size - 2u);
+    return mix(settings.ramp[index], settings.ramp[index + 1u], position - f32(index));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let tick = in.strain / settings.legend_tick;
+    if settings.legend_tick > 0.0 && abs(tick - round(tick)) * settings.legend_tick < 0.006 * settings.max_strain {
+        return vec4<f32>(0.1, 0.1, 0.1, 1.0);
+    }
+    return ramp_color(in.strain);
+}
@@ -1,3 +1,28 @@
+mod bench;
+mod bending;
+mod checkpoint;
+mod clip;
+mod cloth_grid;
+mod debug_lines;
+mod diagnostics;
+mod export;
+mod gpu;
+mod headless;
+mod heatmap;
+mod glb;
+mod material;
+mod membrane;
+mod obj_cloth;
+mod offscreen;
+mod picking;
+mod point_cache;
+mod readback;
+#[cfg(test)]
+mod solver_tests;
+mod spring_chunks;
+mod state_hash;
+mod tearing;
+
 use wgpu_bootstrap::{
     window::Window,
     frame::Frame,
@@ -8,26 +33,44 @@ use wgpu_bootstrap::{
     wgpu,
     cgmath,
     default::Vertex,
-    computation::Computation,
-    texture::create_texture_bind_group,
+    winit::event::{WindowEvent, ElementState, MouseButton, KeyboardInput, VirtualKeyCode},
 };
 
+use checkpoint::Checkpoint;
+use clip::ClipRecorder;
+use cloth_grid::ClothGridBuilder;
+use debug_lines::{DebugBuffers, DebugOverlay, DebugSettings};
+use diagnostics::{Diagnostics, DiagnosticsLog};
+use export::FrameExporter;
+use gpu::{storage_entry, uniform_entry, Gpu};
+use glb::GlbRecorder;
+use heatmap::{RenderMode, RenderSettings};
+use material::Material;
+use picking::Grab;
+use point_cache::{Playback, PointCacheReader, PointCacheWriter};
+use spring_chunks::{spring_chunks, SpringChunk};
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct ComputeData {
     delta_time: f32,
-    number_vertices: f32,
+    number_vertices: u32,
     sphere_radius: f32,
     sphere_center_x: f32,
     sphere_center_y: f32,
     sphere_center_z: f32,
     vertex_mass: f32,
-    structural_stiffness: f32,
-    shear_stiffness: f32,
-    bend_stiffness: f32,
-    structural_damping: f32,
-    shear_damping: f32,
-    bend_damping: f32,
+    grab_index: i32, // index du vertex tenu à la souris, -1 si aucun
+    grab_target_x: f32,
+    grab_target_y: f32,
+    grab_target_z: f32,
+    grab_stiffness: f32,
+    tear_ratio: f32, // un spring se déchire au dela de tear_ratio * rest_length, 0 pour désactiver
+    plastic_yield: f32, // déformation relative au dela de laquelle rest_length change, 0 pour désactiver
+    plastic_rate: f32,
+    dihedral_bending: u32, // 1 pour plier avec l'angle entre les triangles au lieu des bend springs
+    membrane_fem: u32, // 1 pour étirer le tissu avec les éléments finis au lieu des structural et shear springs
+    strain_limit: f32, // les structural springs restent entre (1 - strain_limit) et (1 + strain_limit) * rest_length
 }
 
 #[repr(C)]
@@ -36,12 +79,77 @@ struct Velocity {
     pub velocity: [f32; 3]
 }
 
+// sur le GPU l'état du tissu est un vec4 par sommet, w = 1 pour les positions et 0 pour les vitesses :
+// 16 octets alignés lus d'un coup au lieu du Vertex entier (44 octets) pour n'en utiliser que 12
+fn to_vec4(values: impl IntoIterator<Item = [f32; 3]>, w: f32) -> Vec<[f32; 4]> {
+    values.into_iter().map(|[x, y, z]| [x, y, z, w]).collect()
+}
+
+// the positions of the last step as the third vertex buffer of the cloth, location 5 of cloth.wgsl
+fn position_buffer_layout<'a>() -> wgpu::VertexBufferLayout<'a> {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![5 => Float32x4];
+    wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &ATTRIBUTES,
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct Spring {
-    pub inital_index: f32, // index du points duquel on part
-    pub linked_index: f32, // à qui il est lié
+    pub inital_index: u32, // index du points duquel on part
+    pub linked_index: u32, // à qui il est lié
     pub rest_length: f32,
+    pub stiffness: f32,
+    pub damping: f32,
+    pub spring_type: u32, // SPRING_STRUCTURAL, SPRING_SHEAR, SPRING_BEND ou SPRING_TORN
+}
+
+// index of the first spring of each vertex in a list of springs sorted by vertex, plus the total number of springs at the end
+fn spring_offsets(springs: &[Spring], number_vertices: usize) -> Vec<u32> {
+    let mut offsets = vec![0; number_vertices + 1];
+    for spring in springs {
+        offsets[spring.inital_index as usize + 1] += 1;
+    }
+    for vertex in 0..number_vertices {
+        offsets[vertex + 1] += offsets[vertex];
+    }
+    offsets
+}
+
+// for each vertex, the elements (hinges, triangles) it belongs to, offsets and list packed in a single buffer to save storage bindings:
+// the first number_vertices + 1 values are offsets into the same buffer, the elements of vertex i are between buffer[i] and buffer[i + 1],
+// each one stored as element_index * 4 + role of the vertex in the element
+fn vertex_elements<const N: usize>(elements: &[[u32; N]], number_vertices: usize) -> Vec<u32> {
+    let mut per_vertex: Vec<Vec<u32>> = vec![Vec::new(); number_vertices];
+    for (element_index, element) in elements.iter().enumerate() {
+        for (role, &vertex) in element.iter().enumerate() {
+            per_vertex[vertex as usize].push(element_index as u32 * 4 + role as u32);
+        }
+    }
+
+    let mut packed = Vec::with_capacity(number_vertices + 1);
+    let mut offset = (number_vertices + 1) as u32;
+    for elements_of_vertex in per_vertex.iter() {
+        packed.push(offset);
+        offset += elements_of_vertex.len() as u32;
+    }
+    packed.push(offset);
+    for elements_of_vertex in per_vertex {
+        packed.extend(elements_of_vertex);
+    }
+    packed
+}
+
+// a result of vertex_elements after another list of offsets in the same buffer, its offsets are moved by the length of
+// what comes before so they still point into the whole buffer
+fn concat_vertex_elements(first: Vec<u32>, second: &[u32], number_vertices: usize) -> Vec<u32> {
+    let start = first.len() as u32;
+    let mut packed = first;
+    packed.extend(second[..=number_vertices].iter().map(|offset| offset + start));
+    packed.extend_from_slice(&second[number_vertices + 1..]);
+    packed
 }
 
 
@@ -50,11 +158,16 @@ struct Spring {
 // ==================================================
 
 // we want to change the size of the cloth, the number of vertices and the start position
-const CLOTH_SIZE: f32 = 35.0;
-const CLOTH_VERTICES_PER_ROW: u32 = 25; // the cloth is a square, the minimum is 2
+// the rows of the cloth are stacked along x and each row goes along z
+const CLOTH_WIDTH: f32 = 35.0; // size along z
+const CLOTH_HEIGHT: f32 = 35.0; // size along x
+const CLOTH_ROWS: u32 = 25; // the minimum is 2
+const CLOTH_COLUMNS: u32 = 25; // number of vertices in a row, the minimum is 2
+const CLOTH_OBJ: Option<&str> = None; // path to an OBJ file to use as the cloth instead of the grid, with its UVs
 const CLOTH_CENTER_X: f32 = 0.0;
 const CLOTH_CENTER_Y: f32 = 10.0;
 const CLOTH_CENTER_Z: f32 = 0.0;
+const CLOTH_TEXTURE: &[u8] = include_bytes!("ball_skin.png");
 // Sphere
 const SPHERE_RADIUS: f32 = 10.0;
 const SPHERE_CENTER_X: f32 = 0.0;
@@ -63,16 +176,68 @@ const SPHERE_CENTER_Z: f32 = 0.0;
 
 const VERTEX_MASS: f32 = 0.3;
 // Springs
-const STRUCTURAL_STIFFNESS: f32 = 20.0;
-const SHEAR_STIFFNESS: f32 = 20.0;
-const BEND_STIFFNESS: f32 = 10.0;
-const STRUCTURAL_DAMPING: f32 = 1.0;
-const SHEAR_DAMPING: f32 = 1.0;
-const BEND_DAMPING: f32 = 0.1;
+const CLOTH_MATERIAL: Material = material::COTTON; // stiffness and damping of the springs, see material.rs for the presets
+const MATERIAL_MASK: Option<&str> = None; // image painting regions of the cloth with the materials of MATERIAL_MASK_PALETTE (red channel)
+const MATERIAL_MASK_PALETTE: [Material; 4] = [material::COTTON, material::SILK, material::DENIM, material::LEATHER];
+// Bending
+const DIHEDRAL_BENDING: bool = false; // resist folding with the angle between adjacent triangles instead of the bend springs
+// Membrane
+const MEMBRANE_FEM: bool = false; // stretch the cloth with finite-element triangles (St. Venant-Kirchhoff) instead of the structural and shear springs, see Material::young_modulus
+// Strain limiting
+const STRAIN_LIMITING: bool = false; // keep the structural springs close to their rest length whatever their stiffness, so the cloth doesn't stretch like rubber
+const STRAIN_LIMIT: f32 = 0.1; // a structural spring can be at most 10% longer or shorter than its rest length
+const STRAIN_LIMIT_ITERATIONS: u32 = 4; // more iterations get closer to the limit on the whole cloth
+// Spring types
+const SPRING_STRUCTURAL: u32 = 0;
+const SPRING_SHEAR: u32 = 1;
+const SPRING_BEND: u32 = 2;
+const SPRING_TORN: u32 = 3; // a torn spring stays in the buffer but no longer applies any force
+// Tearing
+const TEARING: bool = false; // structural and shear springs break when stretched too much
+const TEAR_RATIO: f32 = 1.5; // a spring tears when its length is more than TEAR_RATIO * rest_length
+const TEAR_CHECK_INTERVAL: u32 = 10; // number of frames between two updates of the torn triangles
+// only springs tear, the triangles of the membrane can't
+const _: () = assert!(!(TEARING && MEMBRANE_FEM), "TEARING needs the structural and shear springs, it doesn't work with MEMBRANE_FEM");
+// Plasticity
+const PLASTICITY: bool = false; // rest lengths permanently change under sustained strain
+const PLASTIC_YIELD: f32 = 0.1; // the rest length starts to change when the strain is above 10%
+const PLASTIC_RATE: f32 = 0.5; // fraction of the difference between length and rest length absorbed per second
+// Diagnostics
+const DIAGNOSTICS: bool = false; // measure the energies and the momentum after every step (always on with --diagnostics <csv>), see MyApp::diagnostics
+// Heat map
+const HEATMAP_MAX_STRAIN: f32 = 0.2; // strain shown with the last colour of the ramp, the H key or --heatmap shows the heat map instead of the texture
+const HEATMAP_RAMP: [[f32; 3]; 5] = [ // from no stretch to HEATMAP_MAX_STRAIN, 2 to 8 colours
+    [0.0, 0.0, 1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, 1.0, 0.0],
+    [1.0, 1.0, 0.0],
+    [1.0, 0.0, 0.0],
+];
+const HEATMAP_LEGEND_TICK: f32 = 0.05; // a line on the legend every 5% of strain
+// Debug overlay
+const DEBUG_VELOCITY_SCALE: f32 = 0.1; // a velocity is drawn as the distance it goes in 0.1 second
+const DEBUG_NORMAL_LENGTH: f32 = 1.0;
+// Mouse
+const GRAB_STIFFNESS: f32 = 50.0; // stiffness of the spring between the grabbed vertex and the cursor
+const GRAB_MAX_DISTANCE: f32 = 2.0; // a vertex further than this from the cursor ray can't be grabbed
+// Export
+const EXPORT_PATTERN: &str = "export/cloth_####.obj"; // frames written when the export is turned on with the E key, .ply for PLY files
+const EXPORT_INTERVAL: u32 = 5; // number of simulation steps between two exported frames
+const GLB_PATH: &str = "export/cloth.glb"; // animation recorded when the G key is pressed, written when it is pressed again
+const GLB_INTERVAL: u32 = 2; // number of simulation steps between two frames of the animation
+const GLB_FRAMES: u32 = 150; // the animation is written when it reaches this number of frames
+const CACHE_PATH: &str = "export/cloth.cache"; // point cache recorded while the C key is on, played back with --play
+const CACHE_INTERVAL: u32 = 1; // number of simulation steps between two frames of the point cache
+const CHECKPOINT_PATH: &str = "export/checkpoint.state"; // saved with F5 and restored with F9, --restore <path> starts from a checkpoint
+const CACHE_FLAGS: u32 = point_cache::FLAG_VELOCITIES | point_cache::FLAG_QUANTIZED | point_cache::FLAG_COMPRESSED;
+const CLIP_PATH: &str = "export/cloth.gif"; // clip recorded when the R key is pressed, .png for an APNG
+const CLIP_SECONDS: f32 = 5.0; // the clip is written after this many seconds of simulation, or when R is pressed again
+const CLIP_FPS: f32 = 15.0;
 // ==================================================
 
 struct MyApp {
     // "bindgroup" décrivent un ensemble de ressources et comment elles peuvent être accessibles par un shader. Ces ressources peuvent inclure des textures, des buffers de données, des samplers, etc.
+    camera: Camera,
     camera_bind_group: wgpu::BindGroup, // La camera_bind_group est utilisée pour stocker les informations de la caméra, comme la matrice de vue et la matrice de projection, qui peuvent être utilisées pour afficher la scène à partir d'un point de vue spécifique.
     texture_bind_group: wgpu::BindGroup, // La texture_bind_group est utilisée pour stocker les informations de la texture qui seront utilisées pour remplir le tissu, comme les images, les samplers, etc.
     // sphere
@@ -80,25 +245,69 @@ struct MyApp {
     sphere_vertex_buffer: wgpu::Buffer,
     sphere_index_buffer: wgpu::Buffer,
     sphere_indices: Vec<u16>,
+    sphere_vertices: Vec<Vertex>, // gardé pour l'export glTF
+    sphere_center: [f32; 3], // the sphere starts at SPHERE_CENTER and SPHERE_RADIUS but a checkpoint can move it
+    sphere_radius: f32,
     // cloth
     cloth_pipeline: wgpu::RenderPipeline,
-    cloth_vertex_buffer: wgpu::Buffer,
+    cloth_vertex_buffer: wgpu::Buffer, // les normales et tex_coords du rendu, ses positions ne bougent pas
+    cloth_vertices: Vec<Vertex>, // le même sur le CPU, on y remet les positions relues pour les exports
+    // deux copies de l'état du tissu : un step lit cloth_positions_buffers[front] et écrit l'autre, qui devient front à la fin
+    cloth_positions_buffers: [wgpu::Buffer; 2],
     cloth_index_buffer: wgpu::Buffer,
-    cloth_indices: Vec<u16>,
+    cloth_velocities_buffers: [wgpu::Buffer; 2],
+    front: usize, // les buffers du dernier step terminé, ceux qu'on dessine et qu'on relit
+    cloth_indices: Vec<u32>,
+    number_vertices: u32,
+    frame_count: u32,
+    // heat map
+    render_mode: RenderMode,
+    render_settings_buffer: wgpu::Buffer,
+    render_settings_bind_group: wgpu::BindGroup,
+    legend_pipeline: wgpu::RenderPipeline,
+    strain_pipeline: wgpu::ComputePipeline,
+    strain_buffer: wgpu::Buffer,
+    strain_bind_groups: Vec<wgpu::BindGroup>, // un par chunk de spring_chunks, comme les autres bind groups des springs
+    debug_overlay: DebugOverlay,
     // compute
     compute_pipeline: wgpu::ComputePipeline, //étape 2 pipeline
     forces_compute_pipeline: wgpu::ComputePipeline, //étape 2 pipeline
-    compute_vertices_bind_group: wgpu::BindGroup,
+    strain_limit_pipeline: wgpu::ComputePipeline,
+    strain_limit_apply_pipeline: wgpu::ComputePipeline,
+    diagnostics_pipeline: wgpu::ComputePipeline,
+    compute_start_bind_groups: [wgpu::BindGroup; 2], // group 0, lit l'état i (début du step)
+    compute_end_bind_groups: [wgpu::BindGroup; 2], // group 1, écrit l'état i (fin du step)
     compute_data_bind_group: wgpu::BindGroup,
-    compute_velocities_bind_group: wgpu::BindGroup,
     compute_data_buffer: wgpu::Buffer, //étape 3 buffer
     compute_data: ComputeData,
     // spring
-    springs_bind_group: wgpu::BindGroup,
+    springs_buffer: wgpu::Buffer,
+    spring_offsets: Vec<u32>,
+    spring_chunks: Vec<SpringChunk>,
+    springs_bind_groups: Vec<wgpu::BindGroup>,
+    strain_limit_bind_groups: Vec<wgpu::BindGroup>,
+    strain_limit_apply_bind_group: wgpu::BindGroup,
+    // diagnostics
+    diagnostics_bind_groups: Vec<wgpu::BindGroup>,
+    diagnostics_buffer: wgpu::Buffer,
+    diagnostics: Option<Diagnostics>, // mesurées au dernier step si DIAGNOSTICS ou --diagnostics
+    diagnostics_log: Option<DiagnosticsLog>,
+    // mouse
+    cursor_position: (f32, f32),
+    grab: Option<Grab>,
+    grab_target: [f32; 3],
+    // export
+    exporter: Option<FrameExporter>,
+    glb_recorder: Option<GlbRecorder>,
+    cache_writer: Option<PointCacheWriter>,
+    playback: Option<Playback>, // quand un point cache est rejoué il n'y a pas de simulation
+    clip_recorder: Option<ClipRecorder>,
+    simulation_time: f32,
 }
 
 impl MyApp {
-    fn new(context: &Context) -> Self { 
+    // args: the command line without the program name, the tests give none
+    fn new(context: &impl Gpu, args: &[String]) -> Self { 
 
 
 // --------   CAMERA   --------
@@ -107,13 +316,13 @@ impl MyApp {
             eye: (70.0, 50.0, 10.0).into(),
             target: (0.0, 0.0, 0.0).into(),
             up: cgmath::Vector3::unit_y(),
-            aspect: context.get_aspect_ratio(),
+            aspect: context.aspect_ratio(),
             fovy: 20.0,
             znear: 0.1,
             zfar: 100.0, //E100
         };
 
-        let (_camera_buffer, camera_bind_group) = camera.create_camera_bind_group(context); // create_camera_bind_group est une fonction de la librarie de LRK
+        let (_camera_buffer, camera_bind_group) = context.create_camera_bind_group(&camera); // create_camera_bind_group est une fonction de la librarie de LRK
 
 // ==================================================
 
@@ -124,7 +333,7 @@ impl MyApp {
             "Render Pipeline Sphere",
             include_str!("blue.wgsl"),
             &[Vertex::desc()],
-            &[&context.camera_bind_group_layout], // 1 seul binding de la camera car l'autre possiible binding à mettre ici c'est la texture de la sphere mais ici on ne lui donne pas de texture mais juste une couleur uni
+            &[context.camera_bind_group_layout()], // 1 seul binding de la camera car l'autre possiible binding à mettre ici c'est la texture de la sphere mais ici on ne lui donne pas de texture mais juste une couleur uni
             wgpu::PrimitiveTopology::LineList // du coup on utilise lineList et pas TriangleList
         );
 
@@ -148,7 +357,7 @@ impl MyApp {
         // creation des buffers pour la positions de chaques sommets(vertices)
         let sphere_vertex_buffer = context.create_buffer(
             &sphere_vertices,
-            wgpu::BufferUsages::VERTEX
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST // COPY_DST pour déplacer la sphère quand on restaure un checkpoint
         );
 
         // creation des buffers pour la positions de chaques qui permettent de lier les vertices
@@ -162,85 +371,99 @@ impl MyApp {
 
 // --------   CLOTH   --------
 // ==================================================
-        let texture = context.create_texture( //Econtext.create_srgb_texture
+        let texture_bind_group = context.create_texture_bind_group( //Econtext.create_srgb_texture
             "Football",
-            include_bytes!("ball_skin.png"),
+            CLOTH_TEXTURE,
         );
 
-        let texture_bind_group = create_texture_bind_group(context, &texture);
+        // texture or strain heat map, and the colour ramp of the heat map
+        let render_settings_layout = heatmap::create_settings_bind_group_layout(context.device());
+        let render_mode = if args.iter().any(|arg| arg == "--heatmap") { RenderMode::Strain } else { RenderMode::Texture };
+        let render_settings_buffer = context.create_buffer(
+            &[RenderSettings::new(render_mode, HEATMAP_MAX_STRAIN, &HEATMAP_RAMP, HEATMAP_LEGEND_TICK)],
+            wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
+        );
+        let render_settings_bind_group = context.create_bind_group(
+            "Render Settings Bind Group",
+            &render_settings_layout,
+            &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: render_settings_buffer.as_entire_binding(),
+                },
+            ]
+        );
 
         let cloth_pipeline = context.create_render_pipeline( // creation du pipeline pour lier le shader à cette variable
             "Pipeline Cloth",
             include_str!("cloth.wgsl"),
-            &[Vertex::desc()],
+            &[Vertex::desc(), heatmap::strain_buffer_layout(), position_buffer_layout()], // la strain de chaque sommet est dans un deuxième vertex buffer, la position dans un troisième
             &[
-                &context.texture_bind_group_layout,
-                &context.camera_bind_group_layout,
+                context.texture_bind_group_layout(),
+                context.camera_bind_group_layout(),
+                &render_settings_layout,
                 ],
             wgpu::PrimitiveTopology::TriangleList // comment interpreter les vertices en les convertissant en triangle, on fait un triangle car on a une texture
         );
+        // the bar with the colour ramp drawn next to the heat map
+        let legend_pipeline = context.create_render_pipeline(
+            "Pipeline Legend",
+            include_str!("legend.wgsl"),
+            &[],
+            &[&render_settings_layout],
+            wgpu::PrimitiveTopology::TriangleList
+        );
         
         
         // create the cloth ...... comme pour la sphere avec les icosphere mais ici n'existe pas donc on doit créer les vertex nous meme
-        let mut cloth_vertices = Vec::new();
-        let mut cloth_indices: Vec<u16> = Vec::new();
-        
-        // create the vertices
-        for i in 0..CLOTH_VERTICES_PER_ROW { //Esans parenthese du cloth size
-            for j in 0..CLOTH_VERTICES_PER_ROW {
-                cloth_vertices.push(Vertex { 
-                    position: [
-                        CLOTH_CENTER_X + i as f32 * (CLOTH_SIZE / (CLOTH_VERTICES_PER_ROW - 1) as f32) - (CLOTH_SIZE / 2.0),
-                        CLOTH_CENTER_Y,
-                        CLOTH_CENTER_Z + j as f32 * (CLOTH_SIZE / (CLOTH_VERTICES_PER_ROW - 1) as f32) - (CLOTH_SIZE / 2.0),
-                    ],
-                    normal: [0.0, 0.0, 0.0],
-                    tangent: [0.0, 0.0, 0.0],
-                    tex_coords: [ // au liieu d'utiliser des couleurs on utilises des points pour binder la texture qu'on va mettre
-                        i as f32 * (1.0 / (CLOTH_VERTICES_PER_ROW - 1) as f32), // correspond aux .png mais en relatif
-                        j as f32 * (1.0 / (CLOTH_VERTICES_PER_ROW - 1) as f32),
-                    ],
-                });
-            }
-        }
-
-        // create the indices
-        for i in 0..CLOTH_VERTICES_PER_ROW - 1 { //Epareil pour les 2 premiers mais différent dans la maniere créer le carré
-            for j in 0..CLOTH_VERTICES_PER_ROW - 1 {
-                // first triangle
-                cloth_indices.push((i * CLOTH_VERTICES_PER_ROW + j) as u16); // étgape 3 buffer on met les indices de chaque points du vetemetns que l'on voit draw
-                cloth_indices.push((i * CLOTH_VERTICES_PER_ROW + j + 1) as u16);
-                cloth_indices.push(((i + 1) * CLOTH_VERTICES_PER_ROW + j) as u16);
-                // second triangle
-                cloth_indices.push((i * CLOTH_VERTICES_PER_ROW + j + 1) as u16);
-                cloth_indices.push(((i + 1) * CLOTH_VERTICES_PER_ROW + j + 1) as u16);
-                cloth_indices.push(((i + 1) * CLOTH_VERTICES_PER_ROW + j) as u16);
+        // soit on charge un modèle OBJ, soit on crée une grille
+        let cloth_grid = ClothGridBuilder::new(CLOTH_ROWS, CLOTH_COLUMNS)
+            .size(CLOTH_WIDTH, CLOTH_HEIGHT)
+            .center([CLOTH_CENTER_X, CLOTH_CENTER_Y, CLOTH_CENTER_Z])
+            .stretch_springs(!MEMBRANE_FEM) // avec la membrane les structural et shear springs ne servent plus
+            .bend_springs(!DIHEDRAL_BENDING); // pareil pour les bend springs avec le pliage par angle
+        let (cloth_vertices, cloth_indices) = match CLOTH_OBJ {
+            Some(path) => obj_cloth::load_obj(path),
+            None => (cloth_grid.vertices(), cloth_grid.indices()), // u32 et pas u16 pour pouvoir dépasser 65536 vertices
+        };
+        // en lecture d'un point cache, le tissu est celui du cache
+        let mut playback = point_cache::parse_play_args(args.iter().cloned())
+            .map(|path| Playback::new(PointCacheReader::open(&path).expect("could not open the point cache")));
+        let (cloth_vertices, cloth_indices) = match &mut playback {
+            Some(playback) => {
+                assert!(playback.reader.number_frames() > 0, "the point cache has no frame");
+                let frame = playback.reader.read_frame(0).expect("could not read the point cache");
+                (playback.reader.frame_vertices(&frame), playback.reader.indices.clone())
             }
-        }
+            None => (cloth_vertices, cloth_indices),
+        };
+        let number_vertices = cloth_vertices.len() as u32;
 
         // set the default speed of the cloth
         let mut cloth_velocities: Vec<Velocity> = Vec::new();
 
         // Creating a vector of velocities for each vertex in the cloth. chaque sommet recoit une vitesse nulle
-        for _i in cloth_vertices.iter_mut() { // Epareil
+        for _i in cloth_vertices.iter() { // Epareil
             cloth_velocities.push(Velocity {
                 velocity: [0.0, 0.0, 0.0],
             });
         }
 
-        // create a buffer for the cloth
-        let cloth_vertex_buffer = context.create_buffer(
-            &cloth_vertices,
-            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE
-        );
+        // the vertex buffer of the rendering, the simulation doesn't touch it
+        let cloth_vertex_buffer = context.create_buffer(&cloth_vertices, wgpu::BufferUsages::VERTEX);
+        // create the buffers of the state of the cloth, twice: the front one holds the last step and the next step writes the other one
+        let cloth_positions_buffers = [0, 1].map(|_| context.create_buffer(
+            &to_vec4(cloth_vertices.iter().map(|vertex| vertex.position), 1.0),
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST // COPY_SRC pour pouvoir relire les positions sur le CPU (picking), COPY_DST pour rejouer un point cache
+        ));
         let cloth_index_buffer = context.create_buffer(
             &cloth_indices,
-            wgpu::BufferUsages::INDEX
-        );
-        let cloth_velocities_buffer = context.create_buffer(
-            &cloth_velocities,
-            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX
+            wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST // COPY_DST pour enlever les triangles déchirés
         );
+        let cloth_velocities_buffers = [0, 1].map(|_| context.create_buffer(
+            &to_vec4(cloth_velocities.iter().map(|velocity| velocity.velocity), 0.0),
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST // COPY pour le point cache et les checkpoints
+        ));
 
 // ==================================================
 
@@ -248,45 +471,94 @@ impl MyApp {
 // --------   COMPUTE ET FORCE   --------
 // ==================================================
 
+        // les layouts des compute shaders sont écrits à la main. l'état du tissu est lié en lecture seule (state_read_layout)
+        // ou en écriture (state_write_layout), une passe qui ne fait que lire l'état écrit par le step le lie en lecture seule au group 1
+        let state_read_layout = context.create_bind_group_layout("State Read Bind Group Layout", &[storage_entry(0, true), storage_entry(1, true)]);
+        let state_write_layout = context.create_bind_group_layout("State Write Bind Group Layout", &[storage_entry(0, false), storage_entry(1, false)]);
+        let compute_data_layout = context.create_bind_group_layout("Compute Data Bind Group Layout", &[uniform_entry(0)]);
+        // springs (écrits quand ils se déchirent ou se déforment), vertex elements, hinges, membrane triangles et le chunk
+        let springs_layout = context.create_bind_group_layout(
+            "Springs Bind Group Layout",
+            &[storage_entry(0, false), storage_entry(1, true), storage_entry(2, true), storage_entry(3, true), uniform_entry(4)],
+        );
+        // the passes that read the springs and write one value per vertex (or per workgroup): springs, spring offsets, output and chunk
+        let spring_output_layout = context.create_bind_group_layout(
+            "Spring Output Bind Group Layout",
+            &[storage_entry(0, true), storage_entry(1, true), storage_entry(2, false), uniform_entry(3)],
+        );
+        // the strain also reads the membrane triangles
+        let strain_layout = context.create_bind_group_layout(
+            "Strain Bind Group Layout",
+            &[storage_entry(0, true), storage_entry(1, true), storage_entry(2, false), uniform_entry(3), storage_entry(4, true)],
+        );
+        // the diagnostics also read the hinges and the membrane triangles for their energies
+        let diagnostics_layout = context.create_bind_group_layout(
+            "Diagnostics Bind Group Layout",
+            &[storage_entry(0, true), storage_entry(1, true), storage_entry(2, false), uniform_entry(3), storage_entry(4, true), storage_entry(5, true)],
+        );
+        let corrections_layout = context.create_bind_group_layout("Corrections Bind Group Layout", &[storage_entry(2, true)]);
+
         // create the compute pipeline
-        let compute_pipeline = context.create_compute_pipeline( //on assigne le shader à une variable dans le fichier rust
+        let compute_pipeline = context.create_compute_pipeline_with_layouts( //on assigne le shader à une variable dans le fichier rust
             "Compute Pipeline",
             include_str!("compute.wgsl"),
+            &[&state_read_layout, &state_write_layout, &compute_data_layout],
         );
         // create the force compute pipeline
-        let forces_compute_pipeline = context.create_compute_pipeline(
+        let forces_compute_pipeline = context.create_compute_pipeline_with_layouts(
             "Forces Compute Pipeline",
-            include_str!("forces_compute.wgsl")
+            include_str!("forces_compute.wgsl"),
+            &[&state_read_layout, &state_write_layout, &compute_data_layout, &springs_layout],
+        );
+        // create the strain limiting pipelines, run after the integration. the first one only reads the state written by the step
+        let strain_limit_pipeline = context.create_compute_pipeline_with_layouts(
+            "Strain Limit Pipeline",
+            include_str!("strain_limit.wgsl"),
+            &[&state_read_layout, &state_read_layout, &compute_data_layout, &spring_output_layout],
+        );
+        let strain_limit_apply_pipeline = context.create_compute_pipeline_with_layouts(
+            "Strain Limit Apply Pipeline",
+            include_str!("strain_limit_apply.wgsl"),
+            &[&state_read_layout, &state_write_layout, &compute_data_layout, &corrections_layout],
+        );
+        // create the pipeline that measures the strain of each vertex for the heat map, from the state of the last step read-only at group 0
+        let strain_pipeline = context.create_compute_pipeline_with_layouts(
+            "Strain Pipeline",
+            include_str!("strain.wgsl"),
+            &[&state_read_layout, &compute_data_layout, &strain_layout],
+        );
+        // create the pipeline that sums the energies and the momentum. it only binds the state of the last step, read-only at group 0,
+        // to stay within the 8 storage buffers of a shader
+        let diagnostics_pipeline = context.create_compute_pipeline_with_layouts(
+            "Diagnostics Pipeline",
+            include_str!("diagnostics.wgsl"),
+            &[&state_read_layout, &compute_data_layout, &diagnostics_layout],
         );
 
-        // dans cette variables on lie 2 choses : cloth_vertex_buffer (les points 3D de chaque poiints du tissue) et compute_pipeline : le shader qui nous permet de calculer des choses sur les vertex
-        let compute_vertices_bind_group = context.create_bind_group( // toutes ses pipelines dans la doc aller voir https://sotrh.github.io/learn-wgpu/beginner/tutorial3-pipeline/#how-do-we-use-the-shaders
-            "Compute Vertices Bind Group",
-            &compute_pipeline.get_bind_group_layout(0),
+        // les bind groups de l'état du tissu, partagés par tous les compute shaders : le group 0 lit l'état du début du step
+        // et le group 1 écrit le nouveau, un step de l'état 0 vers l'état 1 utilise compute_start_bind_groups[0] et compute_end_bind_groups[1].
+        // comme aucun shader ne lit ce qu'un autre thread écrit pendant le même dispatch, le résultat ne dépend pas de l'ordre des threads
+        let state_bind_group = |label: &str, layout: &wgpu::BindGroupLayout, state: usize| context.create_bind_group( // toutes ses pipelines dans la doc aller voir https://sotrh.github.io/learn-wgpu/beginner/tutorial3-pipeline/#how-do-we-use-the-shaders
+            label,
+            layout,
             &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: cloth_vertex_buffer.as_entire_binding(),
+                    resource: cloth_positions_buffers[state].as_entire_binding(),
                 },
-            ],
-        );
-
-        // dans cette variables on lie 2 choses : cloth_velocities_buffercloth_velocities_buffer et compute_pipeline : le shader qui nous permet de calculer des choses sur les vertex
-        let compute_velocities_bind_group = context.create_bind_group(
-            "Compute Velocities Bind Group",
-            &compute_pipeline.get_bind_group_layout(1),
-            &[
                 wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: cloth_velocities_buffer.as_entire_binding(),
+                    binding: 1,
+                    resource: cloth_velocities_buffers[state].as_entire_binding(),
                 },
             ],
         );
+        let compute_start_bind_groups = [0, 1].map(|state| state_bind_group("Compute Start Bind Group", &state_read_layout, state));
+        let compute_end_bind_groups = [0, 1].map(|state| state_bind_group("Compute End Bind Group", &state_write_layout, state));
 
         // compute data -----------------------------------------------------
         let compute_data = ComputeData {
             delta_time: 0.01,
-            number_vertices: (CLOTH_VERTICES_PER_ROW*CLOTH_VERTICES_PER_ROW) as f32,
+            number_vertices,
 
             sphere_radius: SPHERE_RADIUS,
             sphere_center_x: SPHERE_CENTER_X,
@@ -295,12 +567,20 @@ impl MyApp {
 
             vertex_mass: VERTEX_MASS,
 
-            structural_stiffness: STRUCTURAL_STIFFNESS,
-            shear_stiffness: SHEAR_STIFFNESS,
-            bend_stiffness: BEND_STIFFNESS,
-            structural_damping: STRUCTURAL_DAMPING,
-            shear_damping: SHEAR_DAMPING,
-            bend_damping: BEND_DAMPING,
+            grab_index: -1,
+            grab_target_x: 0.0,
+            grab_target_y: 0.0,
+            grab_target_z: 0.0,
+            grab_stiffness: GRAB_STIFFNESS,
+
+            tear_ratio: if TEARING { TEAR_RATIO } else { 0.0 },
+
+            plastic_yield: if PLASTICITY { PLASTIC_YIELD } else { 0.0 },
+            plastic_rate: PLASTIC_RATE,
+
+            dihedral_bending: DIHEDRAL_BENDING as u32,
+            membrane_fem: MEMBRANE_FEM as u32,
+            strain_limit: STRAIN_LIMIT,
         };
 
         let compute_data_buffer = context.create_buffer( // étape 3 buffer
@@ -311,7 +591,7 @@ impl MyApp {
         // dans cette variables on lie 2 choses : compute_data_buffer et compute_pipeline : le shader qui nous permet de calculer des choses sur les vertex
         let compute_data_bind_group = context.create_bind_group(
             "Compute Data Bind Group",
-            &compute_pipeline.get_bind_group_layout(2), // il s'agit du pipelinelayout de la documentation
+            &compute_data_layout, // il s'agit du pipelinelayout de la documentation
             &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -326,106 +606,241 @@ impl MyApp {
 // --------   SPRINGS   --------
 // ==================================================
 
-        let mut springs: Vec<Spring> = Vec::new(); // variable dans laquelle on va mettre tout les springs ensembles
-
-        for inital_index_iterate in 0..CLOTH_VERTICES_PER_ROW * CLOTH_VERTICES_PER_ROW {
-            let col: i32 = (inital_index_iterate % CLOTH_VERTICES_PER_ROW) as i32; // s'incrémente de 1 à chaque nouvelle itération = nouvelle colonne et se réinitiliase en fin de ligne (principe de la colonne)
-            let row: i32 = (inital_index_iterate / CLOTH_VERTICES_PER_ROW) as i32; // reste à 0 tant que on est pas passé au dessus du nombre de colonne max et la il rereste coincé à 1 et ainsi de suite (principe d'une ligne)
-            // structural springs
-            for j in [-1,1] as [i32; 2] { // boucle ou j vaut d'abord -1 puis +1
-                // col +- 1
-                let mut linked_index_iterate = row * CLOTH_VERTICES_PER_ROW as i32 + col + j;
-                if col + j > CLOTH_VERTICES_PER_ROW as i32 - 1 || col + j < 0 {
-                    linked_index_iterate = (CLOTH_VERTICES_PER_ROW * CLOTH_VERTICES_PER_ROW + 1) as i32;
-                }
-                springs.push(Spring {
-                    inital_index: inital_index_iterate as f32,
-                    linked_index: linked_index_iterate as f32,
-                    rest_length: (CLOTH_SIZE / (CLOTH_VERTICES_PER_ROW - 1) as f32),
-                });
-                // row +- 1
-                linked_index_iterate = (row + j) * CLOTH_VERTICES_PER_ROW as i32 + col;
-                if row + j > CLOTH_VERTICES_PER_ROW as i32 - 1 || row + j < 0 {
-                    linked_index_iterate = (CLOTH_VERTICES_PER_ROW * CLOTH_VERTICES_PER_ROW + 1) as i32;
-                }
-                springs.push(Spring {
-                    inital_index: inital_index_iterate as f32,
-                    linked_index: linked_index_iterate as f32,
-                    rest_length: (CLOTH_SIZE / (CLOTH_VERTICES_PER_ROW - 1) as f32),
-                });
+        // chaque sommet recoit un matériau, soit le même partout soit peint avec le masque
+        let vertex_materials = match MATERIAL_MASK {
+            Some(path) => {
+                let mask = image::open(path).expect("could not open the material mask").to_rgba8();
+                material::paint_materials(&cloth_vertices, &mask, &MATERIAL_MASK_PALETTE)
             }
+            None => vec![CLOTH_MATERIAL; cloth_vertices.len()],
+        };
 
-            
-            // shear springs
-            for j in [-1,1] as [i32; 2] {
-                // col + j and row + j
-                let mut linked_index_iterate = (row + j) * CLOTH_VERTICES_PER_ROW as i32 + col + j;
-                if col + j > CLOTH_VERTICES_PER_ROW as i32 - 1 || col + j < 0 || row + j > CLOTH_VERTICES_PER_ROW as i32 - 1 || row + j < 0 {
-                    linked_index_iterate = (CLOTH_VERTICES_PER_ROW * CLOTH_VERTICES_PER_ROW + 1) as i32;
-                }
-                springs.push(Spring {
-                    inital_index: inital_index_iterate as f32,
-                    linked_index: linked_index_iterate as f32,
-                    rest_length: (CLOTH_SIZE / (CLOTH_VERTICES_PER_ROW - 1) as f32) * 1.41421356237,
-                });
-                // col + j and row - j
-                linked_index_iterate = (row - j) * CLOTH_VERTICES_PER_ROW as i32 + col + j;
-                if col + j > CLOTH_VERTICES_PER_ROW as i32 - 1 || col + j < 0 || row - j > CLOTH_VERTICES_PER_ROW as i32 - 1 || row - j < 0 {
-                    linked_index_iterate = (CLOTH_VERTICES_PER_ROW * CLOTH_VERTICES_PER_ROW + 1) as i32;
-                }
-                springs.push(Spring {
-                    inital_index: inital_index_iterate as f32,
-                    linked_index: linked_index_iterate as f32,
-                    rest_length: (CLOTH_SIZE / (CLOTH_VERTICES_PER_ROW - 1) as f32) * 1.41421356237,
-                });
-            }
-            // bend springs
-            for j in [-1,1] as [i32; 2] {
-                // col +- 2j
-                let mut linked_index_iterate = row * CLOTH_VERTICES_PER_ROW as i32 + col + 2 * j;
-                if col + 2 * j > CLOTH_VERTICES_PER_ROW as i32 - 1 || col + 2 * j < 0 {
-                    linked_index_iterate = (CLOTH_VERTICES_PER_ROW * CLOTH_VERTICES_PER_ROW + 1) as i32;
-                }
-                springs.push(Spring {
-                    inital_index: inital_index_iterate as f32,
-                    linked_index: linked_index_iterate as f32,
-                    rest_length: (CLOTH_SIZE / (CLOTH_VERTICES_PER_ROW - 1) as f32) * 2.0,
-                });
-                // row +- 2j
-                linked_index_iterate = (row + 2 * j) * CLOTH_VERTICES_PER_ROW as i32 + col;
-                if row + 2 * j > CLOTH_VERTICES_PER_ROW as i32 - 1 || row + 2 * j < 0 {
-                    linked_index_iterate = (CLOTH_VERTICES_PER_ROW * CLOTH_VERTICES_PER_ROW + 1) as i32;
-                }
-                springs.push(Spring {
-                    inital_index: inital_index_iterate as f32,
-                    linked_index: linked_index_iterate as f32,
-                    rest_length: (CLOTH_SIZE / (CLOTH_VERTICES_PER_ROW - 1) as f32) * 2.0,
-                });
-            }
-            
-        }
+        // un point cache peut venir d'un autre tissu (un OBJ, une autre grille), ses springs viennent alors de son maillage
+        let mut springs = if CLOTH_OBJ.is_some() || playback.is_some() {
+            obj_cloth::mesh_springs(&cloth_vertices, &cloth_indices, &vertex_materials, !MEMBRANE_FEM, !DIHEDRAL_BENDING)
+        } else {
+            cloth_grid.springs(&vertex_materials)
+        };
+
+        // the springs of vertex i are springs[spring_offsets[i]..spring_offsets[i + 1]]
+        let spring_offsets = spring_offsets(&springs, cloth_vertices.len());
 
-        // create a buffer for the springs
+        // the passes that read the springs run once per chunk of vertices whose springs fit in one binding (see spring_chunks.rs),
+        // a 1024x1024 cloth has 300 MB of springs and a binding is limited to 128 MiB by default
+        let limits = context.device().limits();
+        let spring_chunks = spring_chunks(&spring_offsets, limits.max_storage_buffer_binding_size as u64, limits.min_storage_buffer_offset_alignment as u64);
+        let spring_chunk_buffers: Vec<wgpu::Buffer> = spring_chunks.iter().map(|chunk| context.create_buffer(&[*chunk], wgpu::BufferUsages::UNIFORM)).collect();
+
+        // create a buffer for the springs. with the membrane and the dihedral bending there is no spring at all,
+        // the buffer then holds one zeroed spring that no vertex uses, spring_offsets comes from the empty list
+        if springs.is_empty() { // wgpu refuse les buffers vides
+            springs.push(bytemuck::Zeroable::zeroed());
+        }
         let springs_buffer = context.create_buffer(
             springs.as_slice(),
-            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST, // COPY pour relire et corriger les springs déchirés
+        );
+        // hinges for the dihedral bending and triangles for the membrane, built even when they are not used because the shader always binds them
+        let mut hinges = bending::build_hinges(&cloth_vertices, &cloth_indices, &vertex_materials);
+        let hinge_vertices: Vec<[u32; 4]> = hinges.iter().map(|hinge| [hinge.vertex_0, hinge.vertex_1, hinge.vertex_2, hinge.vertex_3]).collect();
+        let vertex_hinges = vertex_elements(&hinge_vertices, cloth_vertices.len());
+        if hinges.is_empty() { // wgpu refuse les buffers vides
+            hinges.push(bytemuck::Zeroable::zeroed());
+        }
+        let mut membrane_triangles = membrane::build_triangles(&cloth_vertices, &cloth_indices, &vertex_materials);
+        let triangle_vertices: Vec<[u32; 3]> = membrane_triangles.iter().map(|triangle| [triangle.vertex_0, triangle.vertex_1, triangle.vertex_2]).collect();
+        let vertex_triangles = vertex_elements(&triangle_vertices, cloth_vertices.len());
+        if membrane_triangles.is_empty() {
+            membrane_triangles.push(bytemuck::Zeroable::zeroed());
+        }
+
+        let hinges_buffer = context.create_buffer(
+            hinges.as_slice(),
+            wgpu::BufferUsages::STORAGE,
+        );
+        let membrane_triangles_buffer = context.create_buffer(
+            membrane_triangles.as_slice(),
+            wgpu::BufferUsages::STORAGE,
+        );
+        // the spring offsets, then the triangles and the hinges of each vertex, in one buffer (see forces_compute.wgsl).
+        // the other shaders only read the spring offsets at the start of it
+        let vertex_elements = concat_vertex_elements(spring_offsets.clone(), &vertex_triangles, cloth_vertices.len());
+        let vertex_elements = concat_vertex_elements(vertex_elements, &vertex_hinges, cloth_vertices.len());
+        let vertex_elements_buffer = context.create_buffer(
+            vertex_elements.as_slice(),
+            wgpu::BufferUsages::STORAGE,
         );
-        // create a bind group for the springs
-        let springs_bind_group = context.create_bind_group(
+
+        // create the bind groups for the springs
+        let springs_bind_groups: Vec<wgpu::BindGroup> = spring_chunks.iter().zip(&spring_chunk_buffers).map(|(chunk, chunk_buffer)| context.create_bind_group(
             "Sping Bind Group",
-            &compute_pipeline.get_bind_group_layout(3),
+            &springs_layout, // seul le calcul des forces modifie les springs
             &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: springs_buffer.as_entire_binding(),
+                    resource: chunk.springs_binding(&springs_buffer),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: vertex_elements_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: hinges_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: membrane_triangles_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: chunk_buffer.as_entire_binding(),
                 },
             ]
+        )).collect();
+
+        // the strain limiting reads the same springs and writes a correction per vertex
+        let corrections = vec![[0.0f32; 3]; cloth_vertices.len()];
+        let corrections_buffer = context.create_buffer(
+            corrections.as_slice(),
+            wgpu::BufferUsages::STORAGE,
         );
+        let strain_limit_bind_groups: Vec<wgpu::BindGroup> = spring_chunks.iter().zip(&spring_chunk_buffers).map(|(chunk, chunk_buffer)| context.create_bind_group(
+            "Strain Limit Bind Group",
+            &spring_output_layout,
+            &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: chunk.springs_binding(&springs_buffer),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: vertex_elements_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: corrections_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: chunk_buffer.as_entire_binding(),
+                },
+            ]
+        )).collect();
+        let strain_limit_apply_bind_group = context.create_bind_group(
+            "Strain Limit Apply Bind Group",
+            &corrections_layout,
+            &[
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: corrections_buffer.as_entire_binding(),
+                },
+            ]
+        );
+
+        // lines drawn over the cloth to see the springs, the velocities and the normals
+        let mut debug_overlay = DebugOverlay::new(
+            context,
+            DebugBuffers {
+                positions: [&cloth_positions_buffers[0], &cloth_positions_buffers[1]],
+                velocities: [&cloth_velocities_buffers[0], &cloth_velocities_buffers[1]],
+                springs: &springs_buffer,
+                spring_chunks: &spring_chunks,
+                spring_chunk_buffers: &spring_chunk_buffers,
+                membrane_triangles: &membrane_triangles_buffer,
+                vertex_elements: &vertex_elements_buffer,
+            },
+            &spring_offsets,
+            number_vertices,
+            DebugSettings {
+                velocity_scale: DEBUG_VELOCITY_SCALE,
+                normal_length: DEBUG_NORMAL_LENGTH,
+            },
+        );
+        debug_overlay.show_springs = args.iter().any(|arg| arg == "--show-springs");
+        debug_overlay.show_velocities = args.iter().any(|arg| arg == "--show-velocities");
+        debug_overlay.show_normals = args.iter().any(|arg| arg == "--show-normals");
+
+        // the strain of each vertex, written by strain.wgsl and read as a vertex buffer by cloth.wgsl
+        let strains = vec![0.0f32; cloth_vertices.len()];
+        let strain_buffer = context.create_buffer(
+            strains.as_slice(),
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+        );
+        let strain_bind_groups: Vec<wgpu::BindGroup> = spring_chunks.iter().zip(&spring_chunk_buffers).map(|(chunk, chunk_buffer)| context.create_bind_group(
+            "Strain Bind Group",
+            &strain_layout,
+            &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: chunk.springs_binding(&springs_buffer),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: vertex_elements_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: strain_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: chunk_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: membrane_triangles_buffer.as_entire_binding(),
+                },
+            ]
+        )).collect();
+
+        // the diagnostics read the same springs and write one partial sum per workgroup
+        let diagnostics_partials = vec![Diagnostics::default(); (cloth_vertices.len() as f32 / 128.0).ceil() as usize];
+        let diagnostics_buffer = context.create_buffer(
+            diagnostics_partials.as_slice(),
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        );
+        let diagnostics_bind_groups: Vec<wgpu::BindGroup> = spring_chunks.iter().zip(&spring_chunk_buffers).map(|(chunk, chunk_buffer)| context.create_bind_group(
+            "Diagnostics Bind Group",
+            &diagnostics_layout,
+            &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: chunk.springs_binding(&springs_buffer),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: vertex_elements_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: diagnostics_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: chunk_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: hinges_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: membrane_triangles_buffer.as_entire_binding(),
+                },
+            ]
+        )).collect();
 // ==================================================
 
 
 
-        return Self { // on ajoute les renderpipelines, les bindgroup et les buffer à MyApp.... équiavalent à tout en haut
+        // point cache recorded from the start with --cache
+        let cache_writer = point_cache::parse_args(args.iter().cloned(), CACHE_INTERVAL).map(|(path, flags, interval)| {
+            PointCacheWriter::create(&path, &cloth_vertices, &cloth_indices, flags, interval).expect("could not create the point cache")
+        });
+
+        let mut app = Self { // on ajoute les renderpipelines, les bindgroup et les buffer à MyApp.... équiavalent à tout en haut
+            camera,
             camera_bind_group,
             texture_bind_group,
             // sphere
@@ -433,116 +848,730 @@ impl MyApp {
             sphere_vertex_buffer,
             sphere_index_buffer,
             sphere_indices,
+            sphere_vertices,
+            sphere_center: [SPHERE_CENTER_X, SPHERE_CENTER_Y, SPHERE_CENTER_Z],
+            sphere_radius: SPHERE_RADIUS,
             // cloth
             cloth_pipeline,
             cloth_vertex_buffer,
+            cloth_vertices,
+            cloth_positions_buffers,
             cloth_index_buffer,
+            cloth_velocities_buffers,
+            front: 0,
             cloth_indices,
+            number_vertices,
+            frame_count: 0,
+            // heat map
+            render_mode,
+            render_settings_buffer,
+            render_settings_bind_group,
+            legend_pipeline,
+            strain_pipeline,
+            strain_buffer,
+            strain_bind_groups,
+            debug_overlay,
+            exporter: export::parse_args(args.iter().cloned(), EXPORT_INTERVAL),
+            glb_recorder: glb::parse_args(args.iter().cloned(), GLB_INTERVAL, GLB_FRAMES),
+            cache_writer,
+            playback,
+            clip_recorder: clip::parse_args(args.iter().cloned(), CLIP_SECONDS, CLIP_FPS),
+            simulation_time: 0.0,
             // compute
             compute_pipeline,
             forces_compute_pipeline,
-            compute_vertices_bind_group,
-            compute_velocities_bind_group,
+            strain_limit_pipeline,
+            strain_limit_apply_pipeline,
+            diagnostics_pipeline,
+            compute_start_bind_groups,
+            compute_end_bind_groups,
             compute_data_bind_group,
             compute_data_buffer,
             compute_data,
             // springs
-            springs_bind_group,
+            springs_buffer,
+            spring_offsets,
+            spring_chunks,
+            springs_bind_groups,
+            strain_limit_bind_groups,
+            strain_limit_apply_bind_group,
+            // diagnostics
+            diagnostics_bind_groups,
+            diagnostics_buffer,
+            diagnostics: None,
+            diagnostics_log: diagnostics::parse_args(args.iter().cloned()),
+            // mouse
+            cursor_position: (0.0, 0.0),
+            grab: None,
+            grab_target: [0.0, 0.0, 0.0],
         };
+
+        if let Some(path) = checkpoint_arg(args.iter().cloned()) {
+            app.load_checkpoint(context, &path).expect("could not restore the checkpoint");
+        }
+        app
     }
-    
-}
 
-impl Application for MyApp {
+    fn cursor_ray(&self, context: &impl Gpu) -> picking::Ray {
+        let (width, height) = context.size();
+        picking::cursor_ray(&self.camera, self.cursor_position, (width as f32, height as f32))
+    }
 
-// --------   RENDER   --------
-// ==================================================
-    fn render(&self, context: &Context) -> Result<(), wgpu::SurfaceError> {
-        let mut frame = Frame::new(context)?;
-        
-        {
-            let mut render_pass = frame.begin_render_pass(wgpu::Color {r: 0.85, g: 0.85, b: 0.85, a: 1.0});
-            // render the sphere
-            render_pass.set_pipeline(&self.sphere_pipeline);
-            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.sphere_vertex_buffer.slice(..)); // set_vertex_buffer takes two parameters. The first is what buffer slot to use for this vertex buffer. You can have multiple vertex buffers set at a time.
+    // groups 0 and 1 of the compute shaders for a step that writes the buffers `end`, the other ones being the start of the step.
+    fn set_state_bind_groups<'a>(&'a self, compute_pass: &mut wgpu::ComputePass<'a>, end: usize) {
+        compute_pass.set_bind_group(0, &self.compute_start_bind_groups[1 - end], &[]);
+        compute_pass.set_bind_group(1, &self.compute_end_bind_groups[end], &[]);
+    }
 
-            //The second parameter is the slice of the buffer to use. You can store as many objects in a buffer as your hardware allows, so slice allows us to specify which portion of the buffer to use. We use .. to specify the entire buffer.
+    // the cloth of the last step read back from the GPU, its positions with the normals and texture coordinates of the mesh
+    fn read_vertices(&self, context: &impl Gpu) -> Vec<Vertex> {
+        let positions: Vec<[f32; 4]> = readback::read_buffer(context, &self.cloth_positions_buffers[self.front]);
+        self.cloth_vertices.iter().zip(positions).map(|(vertex, [x, y, z, _])| Vertex { position: [x, y, z], ..*vertex }).collect()
+    }
 
-            // le premier argument c'est le slot pris dans le buffer
-            render_pass.set_index_buffer(self.sphere_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            render_pass.draw_indexed(0..self.sphere_indices.len() as u32, 0, 0..1); // dans la doc il utilise sphere_indices.len() en le mettant dans une variable
+    fn read_velocities(&self, context: &impl Gpu) -> Vec<Velocity> {
+        let velocities: Vec<[f32; 4]> = readback::read_buffer(context, &self.cloth_velocities_buffers[self.front]);
+        velocities.iter().map(|&[x, y, z, _]| Velocity { velocity: [x, y, z] }).collect()
+    }
 
-            // render the cloth as a triangle list
-            render_pass.set_pipeline(&self.cloth_pipeline);
-            render_pass.set_bind_group(0, &self.texture_bind_group, &[]);
-            render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.cloth_vertex_buffer.slice(..)); // slice(..) est un raccourci de "cloth_vertex_buffer.slice(0..cloth_vertex_buffer.len())"
-            render_pass.set_index_buffer(self.cloth_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            render_pass.draw_indexed(0..self.cloth_indices.len() as u32, 0, 0..1);
+    // put the positions of these vertices in the last step, their normals and texture coordinates stay those of the mesh
+    fn write_positions(&self, context: &impl Gpu, vertices: &[Vertex]) {
+        context.update_buffer(&self.cloth_positions_buffers[self.front], &to_vec4(vertices.iter().map(|vertex| vertex.position), 1.0));
+    }
+
+    // the current state of the simulation, read back from the GPU
+    pub fn checkpoint(&self, context: &impl Gpu) -> Checkpoint {
+        Checkpoint {
+            compute_data: self.compute_data,
+            time: self.simulation_time,
+            frame_count: self.frame_count,
+            vertices: self.read_vertices(context),
+            velocities: self.read_velocities(context),
+            springs: readback::read_buffer(context, &self.springs_buffer),
+            indices: self.cloth_indices.clone(),
+        }
+    }
+
+    // put a checkpoint back on the GPU, it must come from the same cloth (same vertices and springs)
+    pub fn restore(&mut self, context: &impl Gpu, checkpoint: &Checkpoint) -> std::io::Result<()> {
+        if checkpoint.vertices.len() != self.number_vertices as usize
+            || checkpoint.velocities.len() != self.number_vertices as usize
+            || checkpoint.springs.len() != (*self.spring_offsets.last().unwrap()).max(1) as usize // a cloth without springs has the padding spring
+            || checkpoint.indices.len() != self.cloth_indices.len()
+        {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "the checkpoint was saved with another cloth"));
         }
-        frame.present();
 
+        self.write_positions(context, &checkpoint.vertices);
+        context.update_buffer(&self.cloth_velocities_buffers[self.front], &to_vec4(checkpoint.velocities.iter().map(|velocity| velocity.velocity), 0.0));
+        context.update_buffer(&self.springs_buffer, &checkpoint.springs);
+        self.cloth_indices = checkpoint.indices.clone();
+        context.update_buffer(&self.cloth_index_buffer, &self.cloth_indices);
+        self.simulation_time = checkpoint.time;
+        self.frame_count = checkpoint.frame_count;
+
+        // sphere collider, the mesh is moved to where it was
+        let data = checkpoint.compute_data;
+        let center = cgmath::Vector3::new(data.sphere_center_x, data.sphere_center_y, data.sphere_center_z);
+        let old_center = cgmath::Vector3::from(self.sphere_center);
+        for vertex in self.sphere_vertices.iter_mut() {
+            let offset = cgmath::Vector3::from(vertex.position) - old_center;
+            vertex.position = (center + offset * (data.sphere_radius / self.sphere_radius)).into();
+        }
+        context.update_buffer(&self.sphere_vertex_buffer, &self.sphere_vertices);
+        self.sphere_center = center.into();
+        self.sphere_radius = data.sphere_radius;
+
+        // the vertex that was held stays held at the same place
+        self.grab_target = [data.grab_target_x, data.grab_target_y, data.grab_target_z];
+        self.grab = (data.grab_index >= 0).then(|| Grab {
+            index: data.grab_index as u32,
+            depth: picking::view_depth(&self.camera, cgmath::Point3::from(self.grab_target)),
+        });
+        self.compute_data = data;
+        context.update_buffer(&self.compute_data_buffer, &[data]);
         Ok(())
     }
 
+    pub fn save_checkpoint(&self, context: &impl Gpu, path: &str) -> std::io::Result<()> {
+        self.checkpoint(context).save(path)
+    }
+
+    pub fn load_checkpoint(&mut self, context: &impl Gpu, path: &str) -> std::io::Result<()> {
+        let checkpoint = Checkpoint::load(path)?;
+        self.restore(context, &checkpoint)
+    }
+
+    // read the cloth back from the GPU and grab the vertex closest to the cursor ray
+    fn start_grab(&mut self, context: &impl Gpu) {
+        let cloth_vertices = self.read_vertices(context);
+        let ray = self.cursor_ray(context);
+
+        if let Some(index) = picking::nearest_vertex(&ray, &cloth_vertices, GRAB_MAX_DISTANCE) {
+            let position = cgmath::Point3::from(cloth_vertices[index as usize].position);
+            self.grab = Some(Grab {
+                index,
+                depth: picking::view_depth(&self.camera, position),
+            });
+            self.grab_target = position.into();
+        }
+    }
+
+    // read back the springs torn by the GPU, finish tearing the cloth around them and hide the torn triangles
+    fn update_tears(&mut self, context: &impl Gpu) {
+        let mut springs: Vec<Spring> = readback::read_buffer(context, &self.springs_buffer);
+
+        if tearing::propagate_tears(&mut springs, &self.spring_offsets) {
+            context.update_buffer(&self.springs_buffer, &springs);
+        }
+        if tearing::remove_torn_triangles(&mut self.cloth_indices, &springs, &self.spring_offsets) {
+            context.update_buffer(&self.cloth_index_buffer, &self.cloth_indices);
+        }
+    }
+
+    // read back the cloth and write it as the next frame of the sequence, the export stops if a file can't be written
+    fn export_frame(&mut self, context: &impl Gpu) {
+        let cloth_vertices = self.read_vertices(context);
+        if let Some(exporter) = &mut self.exporter {
+            if let Err(error) = exporter.write_frame(&cloth_vertices, &self.cloth_indices) {
+                eprintln!("could not export the cloth: {}", error);
+                self.exporter = None;
+            }
+        }
+    }
+
+    // read back the cloth for the glTF animation, the file is written once the recording is full
+    fn record_glb_frame(&mut self, context: &impl Gpu) {
+        let cloth_vertices = self.read_vertices(context);
+        if let Some(recorder) = &mut self.glb_recorder {
+            if recorder.record(&cloth_vertices, self.simulation_time) {
+                self.write_glb();
+            }
+        }
+    }
+
+    // stop the recording and write the .glb with the cloth, its texture, the sphere and the animation
+    fn write_glb(&mut self) {
+        if let Some(recorder) = self.glb_recorder.take() {
+            let scene = glb::Scene {
+                cloth_indices: &self.cloth_indices,
+                cloth_texture_png: CLOTH_TEXTURE,
+                sphere_vertices: &self.sphere_vertices,
+                sphere_indices: &self.sphere_indices,
+            };
+            match recorder.write(&scene) {
+                Ok(path) => println!("animation written to {}", path.display()),
+                Err(error) => eprintln!("could not write the animation: {}", error),
+            }
+        }
+    }
+
+    // sum the energies and the momentum of the cloth on the GPU, the workgroups each write a partial sum that is added here
+    pub fn measure_diagnostics(&self, context: &impl Gpu) -> Diagnostics {
+        let mut encoder = context.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Diagnostics Encoder"),
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Diagnostics Pass"),
+            });
+            compute_pass.set_pipeline(&self.diagnostics_pipeline);
+            compute_pass.set_bind_group(0, &self.compute_start_bind_groups[self.front], &[]);
+            compute_pass.set_bind_group(1, &self.compute_data_bind_group, &[]);
+            for (chunk, bind_group) in self.spring_chunks.iter().zip(&self.diagnostics_bind_groups) {
+                compute_pass.set_bind_group(2, bind_group, &[]);
+                compute_pass.dispatch_workgroups(chunk.number_workgroups(), 1, 1);
+            }
+        }
+        context.queue().submit(Some(encoder.finish()));
+
+        let partials: Vec<Diagnostics> = readback::read_buffer(context, &self.diagnostics_buffer);
+        Diagnostics::sum(&partials)
+    }
+
+    // the energies and the momentum after the last step, None unless DIAGNOSTICS or --diagnostics is on
+    pub fn diagnostics(&self) -> Option<Diagnostics> {
+        self.diagnostics
+    }
+
+    // measure the diagnostics of this step and append them to the CSV file, the file stops if it can't be written
+    fn update_diagnostics(&mut self, context: &impl Gpu) {
+        let diagnostics = self.measure_diagnostics(context);
+        self.diagnostics = Some(diagnostics);
+        if let Some(log) = &mut self.diagnostics_log {
+            if let Err(error) = log.write(self.frame_count, self.simulation_time, &diagnostics) {
+                eprintln!("could not write the diagnostics: {}", error);
+                self.diagnostics_log = None;
+            }
+        }
+    }
+
+    // measure the strain of each vertex for the heat map, only needed while it is shown
+    fn compute_strain(&self, context: &impl Gpu) {
+        let mut encoder = context.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Strain Encoder"),
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Strain Pass"),
+            });
+            compute_pass.set_pipeline(&self.strain_pipeline);
+            compute_pass.set_bind_group(0, &self.compute_start_bind_groups[self.front], &[]); // the last step, read-only
+            compute_pass.set_bind_group(1, &self.compute_data_bind_group, &[]);
+            for (chunk, bind_group) in self.spring_chunks.iter().zip(&self.strain_bind_groups) {
+                compute_pass.set_bind_group(2, bind_group, &[]);
+                compute_pass.dispatch_workgroups(chunk.number_workgroups(), 1, 1);
+            }
+        }
+        context.queue().submit(Some(encoder.finish()));
+    }
+
+    // show the cloth with its texture or with the strain heat map
+    pub fn set_render_mode(&mut self, context: &impl Gpu, render_mode: RenderMode) {
+        self.render_mode = render_mode;
+        context.update_buffer(&self.render_settings_buffer, &[RenderSettings::new(render_mode, HEATMAP_MAX_STRAIN, &HEATMAP_RAMP, HEATMAP_LEGEND_TICK)]);
+        if render_mode == RenderMode::Strain {
+            self.compute_strain(context);
+            println!("strain heat map: from 0% at the bottom of the legend to {}% at the top, a line every {}%", HEATMAP_MAX_STRAIN * 100.0, HEATMAP_LEGEND_TICK * 100.0);
+        }
+    }
+
+    // draw the app offscreen for the clip, the clip is written once its duration is recorded
+    fn record_clip_frame(&mut self, context: &impl Gpu, delta_time: f32) {
+        // le recorder sort de l'app le temps de la dessiner
+        if let Some(mut recorder) = self.clip_recorder.take() {
+            if recorder.capture(context, self, delta_time) {
+                Self::write_clip(recorder);
+            } else {
+                self.clip_recorder = Some(recorder);
+            }
+        }
+    }
+
+    // encode the clip in a thread so the viewer doesn't freeze while the palettes are computed
+    fn write_clip(recorder: ClipRecorder) {
+        println!("writing a clip of {} frames", recorder.number_frames());
+        std::thread::spawn(move || match recorder.write() {
+            Ok(path) => println!("clip written to {}", path.display()),
+            Err(error) => eprintln!("could not write the clip: {}", error),
+        });
+    }
+
+    // read back the cloth (and its velocities if the cache keeps them) and append it to the point cache
+    fn cache_frame(&mut self, context: &impl Gpu) {
+        let positions: Vec<[f32; 3]> = self.read_vertices(context).iter().map(|vertex| vertex.position).collect();
+        let velocities: Vec<[f32; 3]> = if self.cache_writer.as_ref().is_some_and(|writer| writer.has_velocities()) {
+            self.read_velocities(context).iter().map(|velocity| velocity.velocity).collect()
+        } else {
+            Vec::new()
+        };
+        if let Some(writer) = &mut self.cache_writer {
+            if let Err(error) = writer.write_frame(self.simulation_time, &positions, &velocities) {
+                eprintln!("could not write the point cache: {}", error);
+                self.cache_writer = None;
+            }
+        }
+    }
+
+    // show the frame of the point cache at the current playback time
+    fn play_cache(&mut self, context: &impl Gpu, delta_time: f32) {
+        if let Some(playback) = &mut self.playback {
+            let frame = playback.advance(delta_time);
+            if playback.shown_frame != Some(frame) {
+                match playback.reader.read_frame(frame) {
+                    Ok(cache_frame) => {
+                        let positions = to_vec4(playback.reader.frame_vertices(&cache_frame).iter().map(|vertex| vertex.position), 1.0);
+                        context.update_buffer(&self.cloth_positions_buffers[self.front], &positions);
+                        // the velocities too when the cache has them, for the debug overlay
+                        if let Some(velocities) = &cache_frame.velocities {
+                            context.update_buffer(&self.cloth_velocities_buffers[self.front], &to_vec4(velocities.iter().copied(), 0.0));
+                        }
+                        playback.shown_frame = Some(frame);
+                    }
+                    Err(error) => eprintln!("could not read frame {} of the point cache: {}", frame, error),
+                }
+            }
+        }
+    }
+
+    // the grabbed vertex is pulled toward the cursor, kept at the depth where it was grabbed
+    fn move_grab(&mut self, context: &impl Gpu) {
+        if let Some(grab) = &self.grab {
+            let ray = self.cursor_ray(context);
+            self.grab_target = picking::point_at_depth(&self.camera, &ray, grab.depth).into();
+        }
+    }
+}
+
+impl MyApp {
+
+// --------   RENDER   --------
+// ==================================================
+    // draw the sphere and the cloth, in the window or in the offscreen texture of the headless mode
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        // render the sphere
+        render_pass.set_pipeline(&self.sphere_pipeline);
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.sphere_vertex_buffer.slice(..)); // set_vertex_buffer takes two parameters. The first is what buffer slot to use for this vertex buffer. You can have multiple vertex buffers set at a time.
+
+        //The second parameter is the slice of the buffer to use. You can store as many objects in a buffer as your hardware allows, so slice allows us to specify which portion of the buffer to use. We use .. to specify the entire buffer.
+
+        // le premier argument c'est le slot pris dans le buffer
+        render_pass.set_index_buffer(self.sphere_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..self.sphere_indices.len() as u32, 0, 0..1); // dans la doc il utilise sphere_indices.len() en le mettant dans une variable
+
+        // render the cloth as a triangle list
+        render_pass.set_pipeline(&self.cloth_pipeline);
+        render_pass.set_bind_group(0, &self.texture_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
+        render_pass.set_bind_group(2, &self.render_settings_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.cloth_vertex_buffer.slice(..)); // slice(..) est un raccourci de "cloth_vertex_buffer.slice(0..cloth_vertex_buffer.len())"
+        render_pass.set_vertex_buffer(1, self.strain_buffer.slice(..));
+        render_pass.set_vertex_buffer(2, self.cloth_positions_buffers[self.front].slice(..));
+        render_pass.set_index_buffer(self.cloth_index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.cloth_indices.len() as u32, 0, 0..1);
+
+        // springs, velocities and normals, if they are turned on
+        self.debug_overlay.draw(render_pass, &self.camera_bind_group, self.front);
+
+        // the legend of the heat map, on top of everything
+        if self.render_mode == RenderMode::Strain {
+            render_pass.set_pipeline(&self.legend_pipeline);
+            render_pass.set_bind_group(0, &self.render_settings_bind_group, &[]);
+            render_pass.draw(0..6, 0..1);
+        }
+    }
+
 // ==================================================
 
 
 // --------   UPDATE   --------
 // ==================================================
-    fn update(&mut self, context: &Context, delta_time: f32) {
+    // one step of the simulation
+    pub fn step(&mut self, context: &impl Gpu, delta_time: f32) {
+        if self.playback.is_some() {
+            self.play_cache(context, delta_time);
+            if self.render_mode == RenderMode::Strain {
+                self.compute_strain(context);
+            }
+            return;
+        }
+
         // update the compute data
         let compute_data = ComputeData {
             delta_time,
-            number_vertices: (CLOTH_VERTICES_PER_ROW*CLOTH_VERTICES_PER_ROW) as f32,
-            sphere_radius: SPHERE_RADIUS,
-            sphere_center_x: SPHERE_CENTER_X,
-            sphere_center_y: SPHERE_CENTER_Y,
-            sphere_center_z: SPHERE_CENTER_Z,
+            number_vertices: self.number_vertices,
+            sphere_radius: self.sphere_radius,
+            sphere_center_x: self.sphere_center[0],
+            sphere_center_y: self.sphere_center[1],
+            sphere_center_z: self.sphere_center[2],
             vertex_mass: VERTEX_MASS,
-            structural_stiffness: STRUCTURAL_STIFFNESS,
-            shear_stiffness: SHEAR_STIFFNESS,
-            bend_stiffness: BEND_STIFFNESS,
-            structural_damping: STRUCTURAL_DAMPING,
-            shear_damping: SHEAR_DAMPING,
-            bend_damping: BEND_DAMPING,
+            grab_index: self.grab.as_ref().map_or(-1, |grab| grab.index as i32),
+            grab_target_x: self.grab_target[0],
+            grab_target_y: self.grab_target[1],
+            grab_target_z: self.grab_target[2],
+            grab_stiffness: GRAB_STIFFNESS,
+            tear_ratio: if TEARING { TEAR_RATIO } else { 0.0 },
+            plastic_yield: if PLASTICITY { PLASTIC_YIELD } else { 0.0 },
+            plastic_rate: PLASTIC_RATE,
+            dihedral_bending: DIHEDRAL_BENDING as u32,
+            membrane_fem: MEMBRANE_FEM as u32,
+            strain_limit: STRAIN_LIMIT,
         };
         context.update_buffer(&self.compute_data_buffer, &[compute_data]);
+        self.compute_data = compute_data;
 
-        let mut computation = Computation::new(context);
+        // the step reads the front buffers and writes the back ones, which are drawn once the step is done
+        let back = 1 - self.front;
+        let mut encoder = context.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Compute Encoder"),
+        });
 
         {
-            let mut compute_pass = computation.begin_compute_pass();
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Compute Pass"),
+            });
             // calculate the forces
             compute_pass.set_pipeline(&self.forces_compute_pipeline);
-            compute_pass.set_bind_group(0, &self.compute_vertices_bind_group, &[]);
-            compute_pass.set_bind_group(1, &self.compute_velocities_bind_group, &[]);
+            self.set_state_bind_groups(&mut compute_pass, back);
             compute_pass.set_bind_group(2, &self.compute_data_bind_group, &[]);
-            compute_pass.set_bind_group(3, &self.springs_bind_group, &[]);
-            compute_pass.dispatch_workgroups(((CLOTH_VERTICES_PER_ROW*CLOTH_VERTICES_PER_ROW) as f64/128.0).ceil() as u32, 1, 1);
+            for (chunk, bind_group) in self.spring_chunks.iter().zip(&self.springs_bind_groups) {
+                compute_pass.set_bind_group(3, bind_group, &[]);
+                compute_pass.dispatch_workgroups(chunk.number_workgroups(), 1, 1);
+            }
 
             // update the positions and collisions
             compute_pass.set_pipeline(&self.compute_pipeline);
-            compute_pass.set_bind_group(0, &self.compute_vertices_bind_group, &[]);
-            compute_pass.set_bind_group(1, &self.compute_velocities_bind_group, &[]);
+            self.set_state_bind_groups(&mut compute_pass, back);
             compute_pass.set_bind_group(2, &self.compute_data_bind_group, &[]);
-            compute_pass.set_bind_group(3, &self.springs_bind_group, &[]);
-            compute_pass.dispatch_workgroups(((CLOTH_VERTICES_PER_ROW*CLOTH_VERTICES_PER_ROW) as f32/128.0).ceil() as u32, 1, 1);
+            compute_pass.dispatch_workgroups((self.number_vertices as f32/128.0).ceil() as u32, 1, 1);
+
+            // pull the structural springs back within the strain limit, moving the positions and the velocities
+            if STRAIN_LIMITING {
+                for _ in 0..STRAIN_LIMIT_ITERATIONS {
+                    compute_pass.set_pipeline(&self.strain_limit_pipeline);
+                    compute_pass.set_bind_group(1, &self.compute_start_bind_groups[back], &[]); // l'état écrit par le step, en lecture seule
+                    for (chunk, bind_group) in self.spring_chunks.iter().zip(&self.strain_limit_bind_groups) {
+                        compute_pass.set_bind_group(3, bind_group, &[]);
+                        compute_pass.dispatch_workgroups(chunk.number_workgroups(), 1, 1);
+                    }
+
+                    compute_pass.set_pipeline(&self.strain_limit_apply_pipeline);
+                    compute_pass.set_bind_group(1, &self.compute_end_bind_groups[back], &[]);
+                    compute_pass.set_bind_group(3, &self.strain_limit_apply_bind_group, &[]);
+                    compute_pass.dispatch_workgroups((self.number_vertices as f32/128.0).ceil() as u32, 1, 1);
+                }
+            }
+        }
+        context.queue().submit(Some(encoder.finish()));
+        self.front = back;
+
+        self.frame_count += 1;
+        self.simulation_time += delta_time;
+        if DIAGNOSTICS || self.diagnostics_log.is_some() {
+            self.update_diagnostics(context);
+        }
+        if self.render_mode == RenderMode::Strain {
+            self.compute_strain(context);
+        }
+        if TEARING && self.frame_count.is_multiple_of(TEAR_CHECK_INTERVAL) {
+            self.update_tears(context);
+        }
+        if self.exporter.as_ref().is_some_and(|exporter| self.frame_count.is_multiple_of(exporter.interval)) {
+            self.export_frame(context);
+        }
+        if self.cache_writer.as_ref().is_some_and(|writer| self.frame_count.is_multiple_of(writer.interval)) {
+            self.cache_frame(context);
+        }
+        if self.glb_recorder.as_ref().is_some_and(|recorder| self.frame_count.is_multiple_of(recorder.interval)) {
+            self.record_glb_frame(context);
         }
-        computation.submit();
     }
 // ==================================================
+}
 
+impl Application for MyApp {
+    fn render(&self, context: &Context) -> Result<(), wgpu::SurfaceError> {
+        let mut frame = Frame::new(context)?;
+        {
+            let mut render_pass = frame.begin_render_pass(wgpu::Color {r: 0.85, g: 0.85, b: 0.85, a: 1.0});
+            self.draw(&mut render_pass);
+        }
+        frame.present();
+
+        Ok(())
+    }
+
+    fn update(&mut self, context: &Context, delta_time: f32) {
+        self.step(context, delta_time);
+        self.record_clip_frame(context, delta_time);
+    }
+
+
+// --------   INPUT   --------
+// ==================================================
+    fn input(&mut self, context: &Context, event: &WindowEvent) {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = (position.x as f32, position.y as f32);
+                self.move_grab(context);
+            }
+            WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. } => {
+                self.start_grab(context);
+            }
+            WindowEvent::MouseInput { state: ElementState::Released, button: MouseButton::Left, .. } => {
+                self.grab = None;
+            }
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::E), .. }, .. } => {
+                // E starts or stops the export, a new export starts again at frame 1
+                self.exporter = match self.exporter {
+                    Some(_) => None,
+                    None => Some(FrameExporter::new(EXPORT_PATTERN, EXPORT_INTERVAL)),
+                };
+            }
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(VirtualKeyCode::G), .. }, .. } => {
+                // G starts recording the glTF animation, pressing it again writes what was recorded
+                match self.glb_recorder {
+                    Some(_) => self.write_glb(),
+                    None => self.glb_recorder = Some(GlbRecorder::new(GLB_PATH, GLB_INTERVAL, GLB_FRAMES)),
+                }
+            }
+            WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, virtual_keycode: Some(key), .. }, .. } => {
+                match key {
+                    // R starts recording a clip of the viewer, pressing it again before the end writes what was recorded
+                    VirtualKeyCode::R => {
+                        match self.clip_recorder.take() {
+                            Some(recorder) => Self::write_clip(recorder),
+                            None => self.clip_recorder = Some(ClipRecorder::new(CLIP_PATH, CLIP_SECONDS, CLIP_FPS)),
+                        }
+                    }
+                    // C starts or stops recording the point cache, a new recording replaces the file
+                    VirtualKeyCode::C if self.playback.is_none() => {
+                        self.cache_writer = match self.cache_writer {
+                            Some(_) => None,
+                            None => match PointCacheWriter::create(CACHE_PATH, &self.read_vertices(context), &self.cloth_indices, CACHE_FLAGS, CACHE_INTERVAL) {
+                                Ok(writer) => Some(writer),
+                                Err(error) => {
+                                    eprintln!("could not create the point cache: {}", error);
+                                    None
+                                }
+                            },
+                        };
+                    }
+                    // H switches between the texture and the strain heat map
+                    VirtualKeyCode::H => {
+                        let render_mode = match self.render_mode {
+                            RenderMode::Texture => RenderMode::Strain,
+                            RenderMode::Strain => RenderMode::Texture,
+                        };
+                        self.set_render_mode(context, render_mode);
+                    }
+                    // S, V and N show or hide the springs, the velocities and the normals
+                    VirtualKeyCode::S => self.debug_overlay.show_springs = !self.debug_overlay.show_springs,
+                    VirtualKeyCode::V => self.debug_overlay.show_velocities = !self.debug_overlay.show_velocities,
+                    VirtualKeyCode::N => self.debug_overlay.show_normals = !self.debug_overlay.show_normals,
+                    // D prints the energies and the momentum of the cloth
+                    VirtualKeyCode::D => {
+                        let diagnostics = self.diagnostics().unwrap_or_else(|| self.measure_diagnostics(context));
+                        println!(
+                            "kinetic {:.3}, gravitational {:.3}, elastic {:.3} (structural {:.3}, shear {:.3}, bend {:.3}, membrane {:.3}, hinge {:.3}), total {:.3}, momentum {:?}",
+                            diagnostics.kinetic_energy,
+                            diagnostics.gravitational_energy,
+                            diagnostics.elastic_energy(),
+                            diagnostics.structural_energy,
+                            diagnostics.shear_energy,
+                            diagnostics.bend_energy,
+                            diagnostics.membrane_energy,
+                            diagnostics.hinge_energy,
+                            diagnostics.total_energy(),
+                            diagnostics.momentum,
+                        );
+                    }
+                    // F5 saves the state of the simulation and F9 goes back to it
+                    VirtualKeyCode::F5 if self.playback.is_none() => {
+                        match self.save_checkpoint(context, CHECKPOINT_PATH) {
+                            Ok(()) => println!("checkpoint saved to {}", CHECKPOINT_PATH),
+                            Err(error) => eprintln!("could not save the checkpoint: {}", error),
+                        }
+                    }
+                    VirtualKeyCode::F9 if self.playback.is_none() => {
+                        if let Err(error) = self.load_checkpoint(context, CHECKPOINT_PATH) {
+                            eprintln!("could not restore the checkpoint: {}", error);
+                        }
+                    }
+                    // pendant la lecture d'un cache : espace pour la pause, les flèches pour avancer ou reculer d'une frame
+                    VirtualKeyCode::Space => {
+                        if let Some(playback) = &mut self.playback {
+                            playback.paused = !playback.paused;
+                        }
+                    }
+                    VirtualKeyCode::Right | VirtualKeyCode::Left => {
+                        if let Some(playback) = &mut self.playback {
+                            playback.step(if *key == VirtualKeyCode::Right { 1 } else { -1 });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+// ==================================================
+
+}
+
+// "--heatmap" starts with the heat map, "--show-springs", "--show-velocities" and "--show-normals" with the debug overlay,
+// "--restore <path>" from a checkpoint
+const OPTIONS: &[(&str, bool)] = &[("--heatmap", false), ("--show-springs", false), ("--show-velocities", false), ("--show-normals", false), ("--restore", true)];
+
+// every option of the command line, with true when a value follows it. each list is next to the parse_args that reads it
+const ALL_OPTIONS: [&[(&str, bool)]; 9] = [
+    OPTIONS,
+    state_hash::OPTIONS,
+    bench::OPTIONS,
+    headless::OPTIONS,
+    export::OPTIONS,
+    glb::OPTIONS,
+    clip::OPTIONS,
+    point_cache::OPTIONS,
+    diagnostics::OPTIONS,
+];
+
+// the parse_args of the modules skip what they don't know, an option that none of them knows is refused here before anything starts
+fn check_args(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    while let Some(arg) = args.next() {
+        match ALL_OPTIONS.iter().flat_map(|options| options.iter()).find(|(name, _)| *name == arg) {
+            Some((_, true)) if args.next().is_none() => return Err(format!("{} needs a value", arg)),
+            Some(_) => {}
+            None => return Err(format!("unknown option {}", arg)),
+        }
+    }
+    Ok(())
+}
+
+// "--restore <path>" starts the simulation from a checkpoint
+fn checkpoint_arg(mut args: impl Iterator<Item = String>) -> Option<String> {
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        if arg == "--restore" {
+            path = args.next();
+        }
+    }
+    path
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(error) = check_args(args.iter().cloned()) {
+        eprintln!("{}", error);
+        std::process::exit(2);
+    }
+
+    // état de la simulation après N steps, pour voir si un changement la modifie
+    if let Some(options) = state_hash::parse_args(args.iter().cloned()) {
+        match state_hash::run(&options, &args) {
+            Ok(hash) => println!("state hash after {} steps: {:016x}", options.steps, hash),
+            Err(error) => {
+                eprintln!("could not compute the state hash: {}", error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // temps d'un step avec l'ancien état (Vertex entier) et les buffers vec4, sur des tissus de plus en plus grands
+    if let Some(options) = bench::parse_args(args.iter().cloned()) {
+        match bench::run(&options) {
+            Ok(results) => {
+                println!("{:>10} {:>18} {:>10} {:>28}", "grid", "layout", "ms/step", "state MB requested/step");
+                for result in results {
+                    let grid = format!("{0}x{0}", result.grid_size);
+                    println!("{:>10} {:>18} {:>10.3} {:>28.1}", grid, result.layout.name(), result.step_time.as_secs_f64() * 1000.0, result.state_requested as f64 / 1e6);
+                }
+            }
+            Err(error) => {
+                eprintln!("could not run the benchmark: {}", error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // sans fenêtre : on simule et on écrit les images directement
+    if let Some(options) = headless::parse_args(args.iter().cloned()) {
+        if let Err(error) = headless::run(&options, &args) {
+            eprintln!("headless rendering failed: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let window = Window::new();
 
 
     let context = window.get_context();
 
-    let my_app = MyApp::new(context);
+    let my_app = MyApp::new(context, &args);
 
     window.run(my_app);
 }
\ No newline at end of file
@@ -0,0 +1,124 @@
+use wgpu_bootstrap::default::Vertex;
+
+// mechanical parameters of a fabric. the warp runs along x, across the rows of the cloth, the weft along z, inside a row
+// and the bias along the diagonals (shear springs)
+#[derive(Copy, Clone, Debug)]
+pub struct Material {
+    pub warp_stiffness: f32,
+    pub weft_stiffness: f32,
+    pub bias_stiffness: f32,
+    pub bend_stiffness: f32,
+    pub warp_damping: f32,
+    pub weft_damping: f32,
+    pub bias_damping: f32,
+    pub bend_damping: f32,
+    // parameters of the finite-element membrane, used instead of the warp, weft and bias springs when it is enabled
+    pub young_modulus: f32,
+    pub poisson_ratio: f32,
+    pub membrane_damping: f32,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum SpringKind {
+    Warp,
+    Weft,
+    Bias,
+    Bend,
+}
+
+// --------   PRESETS   --------
+// ==================================================
+pub const COTTON: Material = Material {
+    warp_stiffness: 20.0,
+    weft_stiffness: 20.0,
+    bias_stiffness: 20.0,
+    bend_stiffness: 10.0,
+    warp_damping: 1.0,
+    weft_damping: 1.0,
+    bias_damping: 1.0,
+    bend_damping: 0.1,
+    young_modulus: 20.0,
+    poisson_ratio: 0.3,
+    membrane_damping: 1.0,
+};
+
+// light and flowing, resists very little to shearing and bending
+pub const SILK: Material = Material {
+    warp_stiffness: 14.0,
+    weft_stiffness: 12.0,
+    bias_stiffness: 5.0,
+    bend_stiffness: 1.0,
+    warp_damping: 0.5,
+    weft_damping: 0.5,
+    bias_damping: 0.3,
+    bend_damping: 0.05,
+    young_modulus: 12.0,
+    poisson_ratio: 0.2,
+    membrane_damping: 0.5,
+};
+
+// twill weave: much stiffer along the warp than along the weft
+pub const DENIM: Material = Material {
+    warp_stiffness: 45.0,
+    weft_stiffness: 30.0,
+    bias_stiffness: 25.0,
+    bend_stiffness: 20.0,
+    warp_damping: 1.5,
+    weft_damping: 1.5,
+    bias_damping: 1.2,
+    bend_damping: 0.3,
+    young_modulus: 40.0,
+    poisson_ratio: 0.3,
+    membrane_damping: 1.5,
+};
+
+// no weave, the same in every direction and hard to fold
+pub const LEATHER: Material = Material {
+    warp_stiffness: 60.0,
+    weft_stiffness: 60.0,
+    bias_stiffness: 55.0,
+    bend_stiffness: 40.0,
+    warp_damping: 2.0,
+    weft_damping: 2.0,
+    bias_damping: 2.0,
+    bend_damping: 0.5,
+    young_modulus: 60.0,
+    poisson_ratio: 0.4,
+    membrane_damping: 2.0,
+};
+// ==================================================
+
+impl Material {
+    // stiffness and damping of one kind of spring
+    pub fn spring_parameters(&self, kind: SpringKind) -> (f32, f32) {
+        match kind {
+            SpringKind::Warp => (self.warp_stiffness, self.warp_damping),
+            SpringKind::Weft => (self.weft_stiffness, self.weft_damping),
+            SpringKind::Bias => (self.bias_stiffness, self.bias_damping),
+            SpringKind::Bend => (self.bend_stiffness, self.bend_damping),
+        }
+    }
+}
+
+// a spring between two regions made of different materials takes the mean of both
+pub fn spring_parameters(vertex_materials: &[Material], vertex_1: usize, vertex_2: usize, kind: SpringKind) -> (f32, f32) {
+    let (stiffness_1, damping_1) = vertex_materials[vertex_1].spring_parameters(kind);
+    let (stiffness_2, damping_2) = vertex_materials[vertex_2].spring_parameters(kind);
+    ((stiffness_1 + stiffness_2) / 2.0, (damping_1 + damping_2) / 2.0)
+}
+
+// material of each vertex, read from the red channel of a mask image at the vertex texture coordinates.
+// a red of 0 selects the first material of the palette and 255 the last one
+pub fn paint_materials(vertices: &[Vertex], mask: &image::RgbaImage, palette: &[Material]) -> Vec<Material> {
+    let (width, height) = mask.dimensions();
+
+    vertices
+        .iter()
+        .map(|vertex| {
+            let x = (vertex.tex_coords[0].clamp(0.0, 1.0) * (width - 1) as f32).round() as u32;
+            let y = (vertex.tex_coords[1].clamp(0.0, 1.0) * (height - 1) as f32).round() as u32;
+            let red = mask.get_pixel(x, y)[0] as f32 / 255.0;
+            palette[(red * (palette.len() - 1) as f32).round() as usize]
+        })
+        .collect()
+}
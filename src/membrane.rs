@@ -0,0 +1,86 @@
+use wgpu_bootstrap::{
+    cgmath::{self, InnerSpace},
+    default::Vertex,
+};
+
+use crate::material::Material;
+
+// one triangle of the finite-element membrane with its rest shape.
+// the rest shape is the triangle laid flat in its own plane: its edges x1 - x0 and x2 - x0 become the columns of a 2x2 matrix Dm,
+// the shader only needs the inverse of Dm, stored column by column
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MembraneTriangle {
+    pub vertex_0: u32,
+    pub vertex_1: u32,
+    pub vertex_2: u32,
+    pub rest_inverse_00: f32, // colonne 0, ligne 0
+    pub rest_inverse_01: f32, // colonne 0, ligne 1
+    pub rest_inverse_10: f32,
+    pub rest_inverse_11: f32,
+    pub rest_area: f32,
+    pub lame_mu: f32, // Lamé parameters of the material, from the Young's modulus and the Poisson ratio
+    pub lame_lambda: f32,
+    pub damping: f32,
+}
+
+fn position(vertices: &[Vertex], index: u32) -> cgmath::Vector3<f32> {
+    cgmath::Vector3::from(vertices[index as usize].position)
+}
+
+// Lamé parameters of a thin sheet (plane stress)
+fn lame_parameters(young_modulus: f32, poisson_ratio: f32) -> (f32, f32) {
+    let mu = young_modulus / (2.0 * (1.0 + poisson_ratio));
+    let lambda = young_modulus * poisson_ratio / (1.0 - poisson_ratio * poisson_ratio);
+    (mu, lambda)
+}
+
+// one membrane element per triangle of the mesh, at rest in the initial shape of the cloth.
+// triangles with no area are skipped, they would have no rest shape
+pub fn build_triangles(vertices: &[Vertex], indices: &[u32], vertex_materials: &[Material]) -> Vec<MembraneTriangle> {
+    let mut triangles = Vec::new();
+
+    for triangle in indices.chunks_exact(3) {
+        let (vertex_0, vertex_1, vertex_2) = (triangle[0], triangle[1], triangle[2]);
+        let x0 = position(vertices, vertex_0);
+        let edge_1 = position(vertices, vertex_1) - x0;
+        let edge_2 = position(vertices, vertex_2) - x0;
+
+        // edges in a 2D frame of the triangle plane whose first axis follows edge_1: Dm = [[a, b], [0, c]]
+        let a = edge_1.magnitude();
+        let b = edge_2.dot(edge_1) / a;
+        let c = edge_1.cross(edge_2).magnitude() / a;
+        if a == 0.0 || c == 0.0 || !c.is_finite() {
+            continue;
+        }
+
+        // the element takes the mean of the materials of its three vertices
+        let (young_modulus, poisson_ratio, damping) = [vertex_0, vertex_1, vertex_2]
+            .iter()
+            .map(|&vertex| &vertex_materials[vertex as usize])
+            .fold((0.0, 0.0, 0.0), |(young_modulus, poisson_ratio, damping), material| {
+                (
+                    young_modulus + material.young_modulus / 3.0,
+                    poisson_ratio + material.poisson_ratio / 3.0,
+                    damping + material.membrane_damping / 3.0,
+                )
+            });
+        let (lame_mu, lame_lambda) = lame_parameters(young_modulus, poisson_ratio);
+
+        triangles.push(MembraneTriangle {
+            vertex_0,
+            vertex_1,
+            vertex_2,
+            rest_inverse_00: 1.0 / a,
+            rest_inverse_01: 0.0,
+            rest_inverse_10: -b / (a * c),
+            rest_inverse_11: 1.0 / c,
+            rest_area: 0.5 * a * c,
+            lame_mu,
+            lame_lambda,
+            damping,
+        });
+    }
+
+    triangles
+}
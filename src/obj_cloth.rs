@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use wgpu_bootstrap::{
+    cgmath::{self, InnerSpace},
+    default::Vertex,
+};
+
+use crate::material::{self, Material, SpringKind};
+use crate::{Spring, SPRING_BEND, SPRING_SHEAR, SPRING_STRUCTURAL};
+
+// load the triangles of an OBJ file as cloth vertices and indices.
+// corners that share a position become a single vertex so the cloth stays in one piece along the UV seams,
+// such a vertex keeps the texture coordinates of the first face that uses it
+pub fn load_obj(path: &str) -> (Vec<Vertex>, Vec<u32>) {
+    let (models, _materials) = tobj::load_obj(path, &tobj::LoadOptions {
+        triangulate: true,
+        ..Default::default()
+    })
+    .expect("could not load the cloth OBJ file");
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for model in models {
+        let mesh = model.mesh;
+        let first_vertex = vertices.len() as u32;
+
+        for position in mesh.positions.chunks_exact(3) {
+            vertices.push(Vertex {
+                position: [position[0], position[1], position[2]],
+                normal: [0.0, 0.0, 0.0],
+                tangent: [0.0, 0.0, 0.0],
+                tex_coords: [0.0, 0.0],
+            });
+        }
+
+        let mut has_tex_coords = vec![false; mesh.positions.len() / 3];
+        for (corner, &index) in mesh.indices.iter().enumerate() {
+            indices.push(first_vertex + index);
+
+            if let Some(&tex_coords_index) = mesh.texcoord_indices.get(corner) {
+                if !has_tex_coords[index as usize] {
+                    has_tex_coords[index as usize] = true;
+                    let tex_coords_index = tex_coords_index as usize;
+                    vertices[(first_vertex + index) as usize].tex_coords = [
+                        mesh.texcoords[tex_coords_index * 2],
+                        1.0 - mesh.texcoords[tex_coords_index * 2 + 1], // en OBJ v monte, dans wgpu il descend
+                    ];
+                }
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+// stretch springs are oriented with the texture: along u they follow the warp, along v the weft, otherwise the bias
+fn edge_kind(vertex_1: &Vertex, vertex_2: &Vertex) -> SpringKind {
+    let du = (vertex_1.tex_coords[0] - vertex_2.tex_coords[0]).abs();
+    let dv = (vertex_1.tex_coords[1] - vertex_2.tex_coords[1]).abs();
+    if du > 2.0 * dv {
+        SpringKind::Warp
+    } else if dv > 2.0 * du {
+        SpringKind::Weft
+    } else {
+        SpringKind::Bias
+    }
+}
+
+fn distance(vertex_1: &Vertex, vertex_2: &Vertex) -> f32 {
+    (cgmath::Vector3::from(vertex_1.position) - cgmath::Vector3::from(vertex_2.position)).magnitude()
+}
+
+// build the springs of a triangle mesh, sorted by vertex:
+// if stretch_springs is true every edge of the mesh is a stretch spring. the two vertices opposite to an edge shared by two triangles are linked too:
+// across a diagonal (a bias edge) they are the other two corners of the quad, a shear spring that needs stretch_springs,
+// across a warp or weft edge they are two quads apart, a bend spring that needs bend_springs.
+// the rest lengths are the distances in the loaded mesh
+pub fn mesh_springs(vertices: &[Vertex], indices: &[u32], vertex_materials: &[Material], stretch_springs: bool, bend_springs: bool) -> Vec<Spring> {
+    let number_vertices = vertices.len();
+
+    // for each edge, the vertices opposite to it in the triangles that use it
+    let mut edges: HashMap<(u32, u32), Vec<u32>> = HashMap::new();
+    for triangle in indices.chunks_exact(3) {
+        for corner in 0..3 {
+            let vertex_1 = triangle[corner];
+            let vertex_2 = triangle[(corner + 1) % 3];
+            let opposite = triangle[(corner + 2) % 3];
+            edges.entry((vertex_1.min(vertex_2), vertex_1.max(vertex_2))).or_default().push(opposite);
+        }
+    }
+
+    let mut stretch_neighbours: Vec<Vec<u32>> = vec![Vec::new(); number_vertices];
+    let mut shear_neighbours: Vec<Vec<u32>> = vec![Vec::new(); number_vertices];
+    let mut bend_neighbours: Vec<Vec<u32>> = vec![Vec::new(); number_vertices];
+    for (&(vertex_1, vertex_2), opposites) in edges.iter() {
+        if stretch_springs {
+            stretch_neighbours[vertex_1 as usize].push(vertex_2);
+            stretch_neighbours[vertex_2 as usize].push(vertex_1);
+        }
+
+        if let [opposite_1, opposite_2] = opposites[..] {
+            let (opposite_neighbours, enabled) = match edge_kind(&vertices[vertex_1 as usize], &vertices[vertex_2 as usize]) {
+                SpringKind::Bias => (&mut shear_neighbours, stretch_springs),
+                _ => (&mut bend_neighbours, bend_springs),
+            };
+            if enabled && !opposite_neighbours[opposite_1 as usize].contains(&opposite_2) {
+                opposite_neighbours[opposite_1 as usize].push(opposite_2);
+                opposite_neighbours[opposite_2 as usize].push(opposite_1);
+            }
+        }
+    }
+
+    let mut springs = Vec::new();
+
+    for vertex in 0..number_vertices {
+        let mut neighbours = stretch_neighbours[vertex].clone();
+        neighbours.sort();
+        let mut shears = shear_neighbours[vertex].clone();
+        shears.sort();
+        let mut bends = bend_neighbours[vertex].clone();
+        bends.sort();
+
+        let stretch_springs = neighbours.into_iter().map(|linked| {
+            let kind = edge_kind(&vertices[vertex], &vertices[linked as usize]);
+            let spring_type = match kind {
+                SpringKind::Bias => SPRING_SHEAR,
+                _ => SPRING_STRUCTURAL,
+            };
+            (linked, kind, spring_type)
+        });
+        let shear_springs = shears.into_iter().map(|linked| (linked, SpringKind::Bias, SPRING_SHEAR));
+        let bend_springs = bends.into_iter().map(|linked| (linked, SpringKind::Bend, SPRING_BEND));
+
+        for (linked, kind, spring_type) in stretch_springs.chain(shear_springs).chain(bend_springs) {
+            let (stiffness, damping) = material::spring_parameters(vertex_materials, vertex, linked as usize, kind);
+            springs.push(Spring {
+                inital_index: vertex as u32,
+                linked_index: linked,
+                rest_length: distance(&vertices[vertex], &vertices[linked as usize]),
+                stiffness,
+                damping,
+                spring_type,
+            });
+        }
+    }
+
+    springs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // two quads side by side, each one cut in two triangles along its diagonal from the top-left corner
+    //   0 - 1 - 2
+    //   | \ | \ |
+    //   3 - 4 - 5
+    fn two_quads() -> (Vec<Vertex>, Vec<u32>) {
+        let vertices = (0..6)
+            .map(|index| {
+                let (row, column) = ((index / 3) as f32, (index % 3) as f32);
+                Vertex {
+                    position: [column, 0.0, row],
+                    normal: [0.0, 1.0, 0.0],
+                    tangent: [1.0, 0.0, 0.0],
+                    tex_coords: [column / 2.0, row],
+                }
+            })
+            .collect();
+        let indices = vec![0, 1, 4, 0, 4, 3, 1, 2, 5, 1, 5, 4];
+        (vertices, indices)
+    }
+
+    fn count(springs: &[Spring], spring_type: u32) -> usize {
+        springs.iter().filter(|spring| spring.spring_type == spring_type).count()
+    }
+
+    #[test]
+    fn opposite_vertices_are_shear_across_a_diagonal_and_bend_across_a_quad_edge() {
+        let (vertices, indices) = two_quads();
+        let springs = mesh_springs(&vertices, &indices, &[material::COTTON; 6], true, true);
+
+        // every spring is in the list of both of its vertices
+        assert_eq!(count(&springs, SPRING_STRUCTURAL), 2 * 7);
+        assert_eq!(count(&springs, SPRING_SHEAR), 2 * 4); // both diagonals of both quads
+        assert_eq!(count(&springs, SPRING_BEND), 2);
+        let bend = springs.iter().find(|spring| spring.spring_type == SPRING_BEND).unwrap();
+        assert_eq!((bend.inital_index, bend.linked_index), (0, 5));
+        let shear_pairs: Vec<(u32, u32)> = springs.iter().filter(|spring| spring.spring_type == SPRING_SHEAR).map(|spring| (spring.inital_index, spring.linked_index)).collect();
+        assert!(shear_pairs.contains(&(1, 3)) && shear_pairs.contains(&(2, 4)));
+    }
+
+    #[test]
+    fn the_membrane_keeps_only_the_bend_springs() {
+        let (vertices, indices) = two_quads();
+        let springs = mesh_springs(&vertices, &indices, &[material::COTTON; 6], false, true);
+        assert_eq!(springs.len(), 2);
+        assert_eq!(count(&springs, SPRING_BEND), 2);
+    }
+}
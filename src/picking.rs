@@ -0,0 +1,75 @@
+use wgpu_bootstrap::{
+    camera::Camera,
+    cgmath::{self, InnerSpace},
+    default::Vertex,
+};
+
+pub struct Ray {
+    pub origin: cgmath::Point3<f32>,
+    pub direction: cgmath::Vector3<f32>, // normalized
+}
+
+// the vertex currently held with the mouse
+pub struct Grab {
+    pub index: u32,
+    pub depth: f32, // distance along the camera axis, the target moves in the plane at this depth
+}
+
+// direction in which the camera looks
+fn camera_forward(camera: &Camera) -> cgmath::Vector3<f32> {
+    (camera.target - camera.eye).normalize()
+}
+
+// build the ray that starts at the camera and goes through the pixel under the cursor
+// cursor and window_size are in pixels, (0, 0) is the top left corner of the window
+pub fn cursor_ray(camera: &Camera, cursor: (f32, f32), window_size: (f32, f32)) -> Ray {
+    // pixel -> normalized device coordinates between -1 and 1, y goes up
+    let ndc_x = 2.0 * cursor.0 / window_size.0 - 1.0;
+    let ndc_y = 1.0 - 2.0 * cursor.1 / window_size.1;
+
+    let forward = camera_forward(camera);
+    let right = forward.cross(camera.up).normalize();
+    let up = right.cross(forward);
+    let tan_half_fovy = (camera.fovy.to_radians() / 2.0).tan();
+
+    let direction = forward
+        + right * (ndc_x * tan_half_fovy * camera.aspect)
+        + up * (ndc_y * tan_half_fovy);
+
+    Ray {
+        origin: camera.eye,
+        direction: direction.normalize(),
+    }
+}
+
+// index of the vertex closest to the ray, ignoring vertices behind the camera or further than max_distance from the ray
+pub fn nearest_vertex(ray: &Ray, vertices: &[Vertex], max_distance: f32) -> Option<u32> {
+    let mut nearest = None;
+    let mut nearest_distance = max_distance;
+
+    for (index, vertex) in vertices.iter().enumerate() {
+        let to_vertex = cgmath::Point3::from(vertex.position) - ray.origin;
+        let along_ray = to_vertex.dot(ray.direction);
+        if along_ray <= 0.0 {
+            continue;
+        }
+        let distance = (to_vertex - ray.direction * along_ray).magnitude();
+        if distance < nearest_distance {
+            nearest_distance = distance;
+            nearest = Some(index as u32);
+        }
+    }
+
+    nearest
+}
+
+// distance of a point along the camera axis
+pub fn view_depth(camera: &Camera, point: cgmath::Point3<f32>) -> f32 {
+    (point - camera.eye).dot(camera_forward(camera))
+}
+
+// point of the ray located at the given depth along the camera axis
+pub fn point_at_depth(camera: &Camera, ray: &Ray, depth: f32) -> cgmath::Point3<f32> {
+    let cos_angle = ray.direction.dot(camera_forward(camera));
+    ray.origin + ray.direction * (depth / cos_angle)
+}
@@ -0,0 +1,472 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use wgpu_bootstrap::default::Vertex;
+
+// binary point cache of a simulation, all numbers little endian:
+//   header: "CLTHCACH", version u32, flags u32, number of vertices u32, number of indices u32,
+//           the indices (u32 each) and the texture coordinates (2 f32 per vertex)
+//   then one block per frame, until the end of the file: time f32, size of the data u32, data.
+// the data of a frame is the positions then, with FLAG_VELOCITIES, the velocities. with FLAG_QUANTIZED each of them
+// is its bounding box (6 f32) followed by 3 u16 per vertex inside that box, otherwise 3 f32 per vertex.
+// with FLAG_COMPRESSED the data is compressed with zlib.
+// the number of frames is not in the header so a cache cut by a crash can still be read up to its last whole frame
+const MAGIC: &[u8; 8] = b"CLTHCACH";
+const VERSION: u32 = 1;
+pub const FLAG_VELOCITIES: u32 = 1;
+pub const FLAG_QUANTIZED: u32 = 2;
+pub const FLAG_COMPRESSED: u32 = 4;
+
+pub struct CacheFrame {
+    pub positions: Vec<[f32; 3]>,
+    pub velocities: Option<Vec<[f32; 3]>>,
+}
+
+fn write_u32(writer: &mut impl Write, value: u32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_f32(writer: &mut impl Write, value: f32) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+// --------   WRITER   --------
+// ==================================================
+pub struct PointCacheWriter {
+    file: BufWriter<File>,
+    flags: u32,
+    number_vertices: usize,
+    pub interval: u32, // number of simulation steps between two cached frames
+}
+
+impl PointCacheWriter {
+    pub fn create(path: &str, vertices: &[Vertex], indices: &[u32], flags: u32, interval: u32) -> io::Result<Self> {
+        if let Some(directory) = std::path::Path::new(path).parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)?;
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+
+        file.write_all(MAGIC)?;
+        write_u32(&mut file, VERSION)?;
+        write_u32(&mut file, flags)?;
+        write_u32(&mut file, vertices.len() as u32)?;
+        write_u32(&mut file, indices.len() as u32)?;
+        for &index in indices {
+            write_u32(&mut file, index)?;
+        }
+        for vertex in vertices {
+            write_f32(&mut file, vertex.tex_coords[0])?;
+            write_f32(&mut file, vertex.tex_coords[1])?;
+        }
+
+        Ok(Self {
+            file,
+            flags,
+            number_vertices: vertices.len(),
+            interval: interval.max(1),
+        })
+    }
+
+    pub fn has_velocities(&self) -> bool {
+        self.flags & FLAG_VELOCITIES != 0
+    }
+
+    // velocities are only written if the cache was created with FLAG_VELOCITIES
+    pub fn write_frame(&mut self, time: f32, positions: &[[f32; 3]], velocities: &[[f32; 3]]) -> io::Result<()> {
+        assert_eq!(positions.len(), self.number_vertices, "the cloth changed its number of vertices");
+
+        let mut data = Vec::new();
+        self.write_vectors(&mut data, positions)?;
+        if self.has_velocities() {
+            self.write_vectors(&mut data, velocities)?;
+        }
+        if self.flags & FLAG_COMPRESSED != 0 {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&data)?;
+            data = encoder.finish()?;
+        }
+
+        write_f32(&mut self.file, time)?;
+        write_u32(&mut self.file, data.len() as u32)?;
+        self.file.write_all(&data)?;
+        self.file.flush() // chaque frame est complète sur le disque
+    }
+
+    fn write_vectors(&self, data: &mut Vec<u8>, vectors: &[[f32; 3]]) -> io::Result<()> {
+        if self.flags & FLAG_QUANTIZED == 0 {
+            for vector in vectors {
+                for &value in vector {
+                    write_f32(data, value)?;
+                }
+            }
+            return Ok(());
+        }
+
+        // each component becomes a u16 between the min and the max of the frame
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for vector in vectors {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(vector[axis]);
+                max[axis] = max[axis].max(vector[axis]);
+            }
+        }
+        for value in min.iter().chain(max.iter()) {
+            write_f32(data, *value)?;
+        }
+        for vector in vectors {
+            for axis in 0..3 {
+                let range = max[axis] - min[axis];
+                let normalized = if range > 0.0 { (vector[axis] - min[axis]) / range } else { 0.0 };
+                data.write_all(&((normalized * u16::MAX as f32).round() as u16).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+// ==================================================
+
+
+// --------   READER   --------
+// ==================================================
+pub struct PointCacheReader {
+    file: BufReader<File>,
+    flags: u32,
+    pub indices: Vec<u32>,
+    pub tex_coords: Vec<[f32; 2]>,
+    frames: Vec<(f32, u64, u32)>, // time, position in the file and size of the data of each frame
+}
+
+impl PointCacheReader {
+    // reads the header and finds where every frame starts, the frames themselves are read on demand
+    pub fn open(path: &str) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a cloth point cache"));
+        }
+        let version = read_u32(&mut file)?;
+        if version != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported point cache version {}", version)));
+        }
+        let flags = read_u32(&mut file)?;
+        let number_vertices = read_u32(&mut file)? as usize;
+        let number_indices = read_u32(&mut file)? as usize;
+        let indices = (0..number_indices).map(|_| read_u32(&mut file)).collect::<io::Result<Vec<_>>>()?;
+        let tex_coords = (0..number_vertices)
+            .map(|_| Ok([read_f32(&mut file)?, read_f32(&mut file)?]))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let end = file.get_ref().metadata()?.len();
+        let mut frames = Vec::new();
+        let mut position = file.stream_position()?;
+        while position + 8 <= end {
+            let time = read_f32(&mut file)?;
+            let size = read_u32(&mut file)?;
+            if position + 8 + size as u64 > end {
+                break; // dernière frame coupée
+            }
+            frames.push((time, position + 8, size));
+            position = file.seek(SeekFrom::Current(size as i64))?;
+        }
+
+        Ok(Self {
+            file,
+            flags,
+            indices,
+            tex_coords,
+            frames,
+        })
+    }
+
+    pub fn number_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn number_vertices(&self) -> usize {
+        self.tex_coords.len()
+    }
+
+    pub fn has_velocities(&self) -> bool {
+        self.flags & FLAG_VELOCITIES != 0
+    }
+
+    pub fn frame_time(&self, frame: usize) -> f32 {
+        self.frames[frame].0
+    }
+
+    pub fn read_frame(&mut self, frame: usize) -> io::Result<CacheFrame> {
+        let (_, position, size) = self.frames[frame];
+        self.file.seek(SeekFrom::Start(position))?;
+        let mut data = vec![0; size as usize];
+        self.file.read_exact(&mut data)?;
+
+        if self.flags & FLAG_COMPRESSED != 0 {
+            let mut decompressed = Vec::new();
+            ZlibDecoder::new(data.as_slice()).read_to_end(&mut decompressed)?;
+            data = decompressed;
+        }
+
+        let mut data = data.as_slice();
+        let positions = self.read_vectors(&mut data)?;
+        let velocities = if self.has_velocities() { Some(self.read_vectors(&mut data)?) } else { None };
+        Ok(CacheFrame { positions, velocities })
+    }
+
+    fn read_vectors(&self, data: &mut &[u8]) -> io::Result<Vec<[f32; 3]>> {
+        let number_vertices = self.number_vertices();
+        if self.flags & FLAG_QUANTIZED == 0 {
+            return (0..number_vertices)
+                .map(|_| Ok([read_f32(data)?, read_f32(data)?, read_f32(data)?]))
+                .collect();
+        }
+
+        let mut bounds = [0.0; 6];
+        for bound in bounds.iter_mut() {
+            *bound = read_f32(data)?;
+        }
+        (0..number_vertices)
+            .map(|_| {
+                let mut vector = [0.0; 3];
+                for (axis, value) in vector.iter_mut().enumerate() {
+                    let mut bytes = [0; 2];
+                    data.read_exact(&mut bytes)?;
+                    let normalized = u16::from_le_bytes(bytes) as f32 / u16::MAX as f32;
+                    *value = bounds[axis] + normalized * (bounds[axis + 3] - bounds[axis]);
+                }
+                Ok(vector)
+            })
+            .collect()
+    }
+
+    // the cloth as it is in a frame, ready to be drawn, with the normals computed from the faces
+    pub fn frame_vertices(&self, frame: &CacheFrame) -> Vec<Vertex> {
+        let mut vertices: Vec<Vertex> = frame
+            .positions
+            .iter()
+            .zip(&self.tex_coords)
+            .map(|(&position, &tex_coords)| Vertex {
+                position,
+                normal: [0.0, 0.0, 0.0],
+                tangent: [0.0, 0.0, 0.0],
+                tex_coords,
+            })
+            .collect();
+        let triangles = crate::export::visible_triangles(&self.indices);
+        let normals = crate::export::vertex_normals(&vertices, &triangles);
+        for (vertex, normal) in vertices.iter_mut().zip(normals) {
+            vertex.normal = normal.into();
+        }
+        vertices
+    }
+}
+// ==================================================
+
+
+// --------   PLAYBACK   --------
+// ==================================================
+// plays a cache in the viewer instead of simulating, at the speed it was recorded
+pub struct Playback {
+    pub reader: PointCacheReader,
+    pub frame: usize,
+    pub time: f32,
+    pub paused: bool,
+    pub shown_frame: Option<usize>, // frame in the vertex buffer, it is only uploaded again when it changes
+}
+
+impl Playback {
+    pub fn new(reader: PointCacheReader) -> Self {
+        let time = if reader.number_frames() > 0 { reader.frame_time(0) } else { 0.0 };
+        Self {
+            reader,
+            frame: 0,
+            time,
+            paused: false,
+            shown_frame: None,
+        }
+    }
+
+    // advance the time and return the frame to show, the playback loops at the end of the cache
+    pub fn advance(&mut self, delta_time: f32) -> usize {
+        let number_frames = self.reader.number_frames();
+        if number_frames == 0 {
+            return 0;
+        }
+        if !self.paused {
+            self.time += delta_time;
+            if self.time > self.reader.frame_time(number_frames - 1) {
+                self.time = self.reader.frame_time(0);
+            }
+        }
+        // dernière frame dont le temps est passé
+        self.frame = (0..number_frames)
+            .rev()
+            .find(|&frame| self.reader.frame_time(frame) <= self.time)
+            .unwrap_or(0);
+        self.frame
+    }
+
+    // step one frame forward or backward (scrubbing), the playback is paused
+    pub fn step(&mut self, frames: i64) {
+        let number_frames = self.reader.number_frames() as i64;
+        if number_frames == 0 {
+            return;
+        }
+        self.paused = true;
+        self.frame = (self.frame as i64 + frames).rem_euclid(number_frames) as usize;
+        self.time = self.reader.frame_time(self.frame);
+    }
+}
+// ==================================================
+
+// "--cache <path>" records a point cache from the start, "--cache-every <steps>" changes the interval,
+// "--cache-velocities", "--cache-quantize" and "--cache-compress" choose what is stored and how, "--play <path>" plays a cache back
+pub const OPTIONS: &[(&str, bool)] = &[
+    ("--cache", true),
+    ("--cache-every", true),
+    ("--cache-velocities", false),
+    ("--cache-quantize", false),
+    ("--cache-compress", false),
+    ("--play", true),
+];
+
+pub fn parse_args(mut args: impl Iterator<Item = String>, default_interval: u32) -> Option<(String, u32, u32)> {
+    let mut path = None;
+    let mut interval = default_interval;
+    let mut flags = 0;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cache" => path = args.next(),
+            "--cache-every" => interval = args.next().and_then(|value| value.parse().ok()).expect("--cache-every needs a number of steps"),
+            "--cache-velocities" => flags |= FLAG_VELOCITIES,
+            "--cache-quantize" => flags |= FLAG_QUANTIZED,
+            "--cache-compress" => flags |= FLAG_COMPRESSED,
+            _ => {}
+        }
+    }
+
+    path.map(|path| (path, flags, interval))
+}
+
+// "--play <path>" opens a point cache to play it back
+pub fn parse_play_args(mut args: impl Iterator<Item = String>) -> Option<String> {
+    let mut path = None;
+    while let Some(arg) = args.next() {
+        if arg == "--play" {
+            path = args.next();
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertices() -> Vec<Vertex> {
+        (0..12)
+            .map(|index| Vertex {
+                position: [index as f32, 0.0, 0.0],
+                normal: [0.0, 1.0, 0.0],
+                tangent: [1.0, 0.0, 0.0],
+                tex_coords: [(index % 4) as f32 / 3.0, (index / 4) as f32 / 2.0],
+            })
+            .collect()
+    }
+
+    fn frame(frame: usize) -> (Vec<[f32; 3]>, Vec<[f32; 3]>) {
+        let positions = (0..12).map(|index| [index as f32 * 0.1, -(frame as f32) * 0.25, (index * frame) as f32 * 0.01]).collect();
+        let velocities = (0..12).map(|index| [0.0, -(frame as f32), index as f32 * 0.5]).collect();
+        (positions, velocities)
+    }
+
+    fn max_difference(vectors: &[[f32; 3]], other_vectors: &[[f32; 3]]) -> f32 {
+        vectors.iter().zip(other_vectors).flat_map(|(vector, other)| (0..3).map(move |axis| (vector[axis] - other[axis]).abs())).fold(0.0, f32::max)
+    }
+
+    // write 3 frames with these flags, read them back and return the reader
+    fn round_trip(name: &str, flags: u32, tolerance: f32) -> PointCacheReader {
+        let path = std::env::temp_dir().join(format!("cloth_point_cache_{}_{}.cache", name, std::process::id()));
+        let path = path.to_str().unwrap();
+        let vertices = vertices();
+        let indices = vec![0, 1, 4, 1, 5, 4, 2, 3, 6];
+        let mut writer = PointCacheWriter::create(path, &vertices, &indices, flags, 1).unwrap();
+        for index in 0..3 {
+            let (positions, velocities) = frame(index);
+            writer.write_frame(index as f32 / 60.0, &positions, &velocities).unwrap();
+        }
+        drop(writer);
+
+        let mut reader = PointCacheReader::open(path).unwrap();
+        assert_eq!(reader.indices, indices);
+        assert_eq!(reader.tex_coords, vertices.iter().map(|vertex| vertex.tex_coords).collect::<Vec<_>>());
+        assert_eq!(reader.number_frames(), 3);
+        for index in 0..3 {
+            let (positions, velocities) = frame(index);
+            let cache_frame = reader.read_frame(index).unwrap();
+            assert_eq!(reader.frame_time(index), index as f32 / 60.0);
+            assert!(max_difference(&cache_frame.positions, &positions) <= tolerance);
+            match cache_frame.velocities {
+                Some(cached_velocities) => assert!(max_difference(&cached_velocities, &velocities) <= tolerance),
+                None => assert_eq!(flags & FLAG_VELOCITIES, 0),
+            }
+        }
+        fs::remove_file(path).unwrap();
+        reader
+    }
+
+    #[test]
+    fn plain_frames_are_read_back_exactly() {
+        let reader = round_trip("plain", FLAG_VELOCITIES, 0.0);
+        assert!(reader.has_velocities());
+        assert!(!round_trip("positions", 0, 0.0).has_velocities());
+    }
+
+    #[test]
+    fn quantized_frames_are_read_back_within_a_step() {
+        // the widest box is 5.5 long (velocities along z), a u16 step is 5.5 / 65535
+        round_trip("quantized", FLAG_VELOCITIES | FLAG_QUANTIZED, 5.5 / 65535.0);
+    }
+
+    #[test]
+    fn compressed_frames_are_read_back_exactly() {
+        round_trip("compressed", FLAG_VELOCITIES | FLAG_COMPRESSED, 0.0);
+        round_trip("compressed_quantized", FLAG_VELOCITIES | FLAG_COMPRESSED | FLAG_QUANTIZED, 5.5 / 65535.0);
+    }
+
+    #[test]
+    fn a_truncated_cache_is_read_up_to_its_last_whole_frame() {
+        let path = std::env::temp_dir().join(format!("cloth_point_cache_truncated_{}.cache", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut writer = PointCacheWriter::create(path, &vertices(), &[0, 1, 4], FLAG_COMPRESSED, 1).unwrap();
+        for index in 0..3 {
+            let (positions, velocities) = frame(index);
+            writer.write_frame(index as f32, &positions, &velocities).unwrap();
+        }
+        drop(writer);
+
+        // the last frame loses its last byte, as if the simulation had crashed while writing it
+        let length = fs::metadata(path).unwrap().len();
+        File::options().write(true).open(path).unwrap().set_len(length - 1).unwrap();
+        let mut reader = PointCacheReader::open(path).unwrap();
+        assert_eq!(reader.number_frames(), 2);
+        assert_eq!(reader.read_frame(1).unwrap().positions, frame(1).0);
+        fs::remove_file(path).unwrap();
+    }
+}
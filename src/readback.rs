@@ -0,0 +1,31 @@
+use wgpu_bootstrap::wgpu;
+
+use crate::gpu::Gpu;
+
+// copy a GPU buffer into a staging buffer and wait until it can be read on the CPU
+// the source buffer must have been created with wgpu::BufferUsages::COPY_SRC
+pub fn read_buffer<T: bytemuck::Pod>(context: &impl Gpu, buffer: &wgpu::Buffer) -> Vec<T> {
+    let size = buffer.size();
+
+    let staging_buffer = context.device().create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Staging Buffer"),
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = context.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Readback Encoder"),
+    });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging_buffer, 0, size);
+    context.queue().submit(Some(encoder.finish()));
+
+    let buffer_slice = staging_buffer.slice(..);
+    buffer_slice.map_async(wgpu::MapMode::Read, |_| {});
+    context.device().poll(wgpu::Maintain::Wait); // on bloque jusqu'à ce que le GPU ait fini la copie
+
+    let data = bytemuck::cast_slice(&buffer_slice.get_mapped_range()).to_vec();
+    staging_buffer.unmap();
+
+    data
+}
@@ -0,0 +1,300 @@
+// regression tests of the GPU solver: the simulation runs through wgpu on a software adapter (llvmpipe, lavapipe, WARP)
+// and is compared after a few steps with a CPU version of the same step. without any adapter the tests say so and pass
+
+use std::sync::Mutex;
+
+use wgpu_bootstrap::cgmath::{InnerSpace, Vector3};
+
+use crate::headless::HeadlessContext;
+use crate::state_hash;
+use crate::*;
+
+const DELTA_TIME: f32 = 1.0 / 60.0;
+const GRAVITY: f32 = 9.81; // the same as forces_compute.wgsl
+
+// un seul device à la fois, le backend GL n'aime pas en avoir plusieurs en parallèle
+static GPU: Mutex<()> = Mutex::new(());
+
+fn context() -> Option<HeadlessContext> {
+    match HeadlessContext::new(64, 64, true).or_else(|_| HeadlessContext::new(64, 64, false)) {
+        Ok(context) => Some(context),
+        Err(error) => {
+            eprintln!("skipped, no GPU adapter: {}", error);
+            None
+        }
+    }
+}
+
+// the CPU reference covers the mass-spring model, not the dihedral bending, the membrane, the tears or the plasticity
+fn reference_covers_configuration() -> bool {
+    if DIHEDRAL_BENDING || MEMBRANE_FEM || TEARING || PLASTICITY {
+        eprintln!("skipped, the CPU reference only has the mass-spring model");
+        return false;
+    }
+    true
+}
+
+// the same step as forces_compute.wgsl, compute.wgsl, strain_limit.wgsl and strain_limit_apply.wgsl, on the CPU
+struct Reference {
+    positions: Vec<Vector3<f32>>,
+    velocities: Vec<Vector3<f32>>,
+    springs: Vec<Spring>,
+    offsets: Vec<u32>,
+}
+
+impl Reference {
+    fn new(app: &MyApp, context: &HeadlessContext) -> Self {
+        let checkpoint = app.checkpoint(context);
+        Reference {
+            positions: checkpoint.vertices.iter().map(|vertex| Vector3::from(vertex.position)).collect(),
+            velocities: checkpoint.velocities.iter().map(|velocity| Vector3::from(velocity.velocity)).collect(),
+            springs: checkpoint.springs,
+            offsets: app.spring_offsets.clone(),
+        }
+    }
+
+    fn step(&mut self, sphere_center: Vector3<f32>, sphere_radius: f32) {
+        // forces, with the velocities of the start of the step
+        let old_velocities = self.velocities.clone();
+        for vertex in 0..self.positions.len() {
+            let mut force_sum = Vector3::new(0.0, 0.0, 0.0);
+            for spring in &self.springs[self.offsets[vertex] as usize..self.offsets[vertex + 1] as usize] {
+                if spring.spring_type == SPRING_TORN {
+                    continue;
+                }
+                let offset = self.positions[spring.inital_index as usize] - self.positions[spring.linked_index as usize];
+                let stretch = offset.magnitude() - spring.rest_length;
+                let direction = offset.normalize();
+                let relative = old_velocities[spring.inital_index as usize] - old_velocities[spring.linked_index as usize];
+                let relative_velocity = relative.magnitude();
+
+                force_sum += -spring.stiffness * stretch * direction;
+                if spring.spring_type == SPRING_BEND {
+                    force_sum += -spring.damping * relative_velocity * direction;
+                }
+                if relative_velocity != 0.0 {
+                    force_sum += -spring.damping * relative_velocity * relative.normalize();
+                }
+            }
+            force_sum.y += -GRAVITY * VERTEX_MASS;
+            self.velocities[vertex] += force_sum / VERTEX_MASS * DELTA_TIME;
+        }
+
+        // integration and collision with the sphere
+        for vertex in 0..self.positions.len() {
+            self.positions[vertex] += self.velocities[vertex] * DELTA_TIME;
+            let distance = (self.positions[vertex] - sphere_center).magnitude();
+            if distance < sphere_radius {
+                let normal = (self.positions[vertex] - sphere_center).normalize();
+                self.positions[vertex] += normal * (sphere_radius - distance);
+                self.velocities[vertex] = Vector3::new(0.0, 0.0, 0.0);
+            }
+        }
+
+        if STRAIN_LIMITING {
+            for _ in 0..STRAIN_LIMIT_ITERATIONS {
+                let corrections: Vec<Vector3<f32>> = (0..self.positions.len()).map(|vertex| self.strain_correction(vertex)).collect();
+                for (vertex, correction) in corrections.into_iter().enumerate() {
+                    self.positions[vertex] += correction;
+                    self.velocities[vertex] += correction / DELTA_TIME;
+                }
+            }
+        }
+    }
+
+    fn strain_correction(&self, vertex: usize) -> Vector3<f32> {
+        let mut correction = Vector3::new(0.0, 0.0, 0.0);
+        let mut number_constraints = 0;
+        for spring in &self.springs[self.offsets[vertex] as usize..self.offsets[vertex + 1] as usize] {
+            if spring.spring_type != SPRING_STRUCTURAL {
+                continue;
+            }
+            let offset = self.positions[vertex] - self.positions[spring.linked_index as usize];
+            let distance = offset.magnitude();
+            let limited_distance = distance.clamp((1.0 - STRAIN_LIMIT) * spring.rest_length, (1.0 + STRAIN_LIMIT) * spring.rest_length);
+            if distance > 0.0 && limited_distance != distance {
+                correction += 0.5 * (limited_distance - distance) * offset / distance;
+                number_constraints += 1;
+            }
+        }
+        if number_constraints > 0 {
+            correction /= number_constraints as f32;
+        }
+        correction
+    }
+}
+
+fn gpu_positions(app: &MyApp, context: &HeadlessContext) -> Vec<Vector3<f32>> {
+    app.checkpoint(context).vertices.iter().map(|vertex| Vector3::from(vertex.position)).collect()
+}
+
+fn max_distance(positions: &[Vector3<f32>], other_positions: &[Vector3<f32>]) -> f32 {
+    positions.iter().zip(other_positions).map(|(position, other)| (position - other).magnitude()).fold(0.0, f32::max)
+}
+
+// run the same steps on the GPU and on the CPU, and return how far apart the cloths end up
+fn compare_with_reference(app: &mut MyApp, context: &HeadlessContext, steps: u32) -> f32 {
+    let mut reference = Reference::new(app, context);
+    for _ in 0..steps {
+        app.step(context, DELTA_TIME);
+        reference.step(Vector3::from(app.sphere_center), app.sphere_radius);
+    }
+    max_distance(&gpu_positions(app, context), &reference.positions)
+}
+
+#[test]
+fn springs_are_symmetric_and_at_rest() {
+    let _gpu = GPU.lock().unwrap_or_else(|error| error.into_inner());
+    let Some(context) = context() else { return };
+    let app = MyApp::new(&context, &[]);
+    let checkpoint = app.checkpoint(&context);
+
+    for (vertex, window) in app.spring_offsets.windows(2).enumerate() {
+        for spring in &checkpoint.springs[window[0] as usize..window[1] as usize] {
+            assert_eq!(spring.inital_index as usize, vertex, "a spring is listed under the wrong vertex");
+            assert!((spring.linked_index as usize) < checkpoint.vertices.len(), "a spring goes to a vertex that doesn't exist");
+
+            // the same spring seen from the other vertex
+            let reverse = checkpoint.springs.iter().find(|other| other.inital_index == spring.linked_index && other.linked_index == spring.inital_index);
+            let reverse = reverse.expect("a spring has no reverse spring");
+            assert_eq!(reverse.spring_type, spring.spring_type);
+            assert!((reverse.rest_length - spring.rest_length).abs() < 1e-5);
+
+            // the cloth starts at rest
+            let position_1 = Vector3::from(checkpoint.vertices[spring.inital_index as usize].position);
+            let position_2 = Vector3::from(checkpoint.vertices[spring.linked_index as usize].position);
+            assert!(((position_1 - position_2).magnitude() - spring.rest_length).abs() < 1e-4 * spring.rest_length.max(1.0));
+        }
+    }
+}
+
+#[test]
+fn springs_pull_a_displaced_vertex_like_the_cpu() {
+    let _gpu = GPU.lock().unwrap_or_else(|error| error.into_inner());
+    let Some(context) = context() else { return };
+    if !reference_covers_configuration() {
+        return;
+    }
+    let mut app = MyApp::new(&context, &[]);
+    app.sphere_center = [0.0, -1000.0, 0.0]; // no collision
+
+    // lift a vertex in the middle of the cloth, its springs pull it back
+    let mut checkpoint = app.checkpoint(&context);
+    let middle = checkpoint.vertices.len() / 2;
+    checkpoint.vertices[middle].position[1] += 1.0;
+    app.restore(&context, &checkpoint).unwrap();
+
+    let distance = compare_with_reference(&mut app, &context, 30);
+    assert!(distance < 1e-2, "the GPU and the CPU are {} apart", distance);
+}
+
+#[test]
+fn sphere_collision_matches_the_cpu() {
+    let _gpu = GPU.lock().unwrap_or_else(|error| error.into_inner());
+    let Some(context) = context() else { return };
+    if !reference_covers_configuration() {
+        return;
+    }
+    let mut app = MyApp::new(&context, &[]);
+
+    let distance = compare_with_reference(&mut app, &context, 60);
+    assert!(distance < 5e-2, "the GPU and the CPU are {} apart", distance);
+
+    // the strain limiting runs after the collision and can push a vertex a little inside the sphere, not more
+    let sphere_center = Vector3::from(app.sphere_center);
+    for position in gpu_positions(&app, &context) {
+        assert!((position - sphere_center).magnitude() > 0.98 * app.sphere_radius, "a vertex went through the sphere");
+    }
+}
+
+#[test]
+fn free_fall_follows_gravity() {
+    let _gpu = GPU.lock().unwrap_or_else(|error| error.into_inner());
+    let Some(context) = context() else { return };
+    let mut app = MyApp::new(&context, &[]);
+    app.sphere_center = [0.0, -1000.0, 0.0]; // no collision
+    let start = gpu_positions(&app, &context);
+
+    let steps = 30;
+    for _ in 0..steps {
+        app.step(&context, DELTA_TIME);
+    }
+
+    // the springs stay at rest, every vertex falls the same way: with semi-implicit Euler the velocity after n steps
+    // is -g n dt and the fall is g dt² n (n + 1) / 2
+    let checkpoint = app.checkpoint(&context);
+    let n = steps as f32;
+    let fall = GRAVITY * DELTA_TIME * DELTA_TIME * n * (n + 1.0) / 2.0;
+    for ((vertex, velocity), start) in checkpoint.vertices.iter().zip(&checkpoint.velocities).zip(&start) {
+        assert!((velocity.velocity[1] + GRAVITY * n * DELTA_TIME).abs() < 1e-3, "velocity {:?}", velocity.velocity);
+        assert!((vertex.position[1] - (start.y - fall)).abs() < 1e-3, "fell to {} instead of {}", vertex.position[1], start.y - fall);
+        assert!((vertex.position[0] - start.x).abs() < 1e-4 && (vertex.position[2] - start.z).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn deterministic_runs_give_the_same_hash() {
+    let _gpu = GPU.lock().unwrap_or_else(|error| error.into_inner());
+    let Some(context) = context() else { return };
+
+    let mut hashes = Vec::new();
+    for _ in 0..2 {
+        let mut app = MyApp::new(&context, &[]);
+        for _ in 0..60 {
+            app.step(&context, DELTA_TIME);
+        }
+        hashes.push(state_hash::hash(&app.checkpoint(&context)));
+    }
+    assert_eq!(hashes[0], hashes[1]);
+}
+
+#[test]
+fn one_step_is_read_from_the_buffers_it_wrote() {
+    let _gpu = GPU.lock().unwrap_or_else(|error| error.into_inner());
+    let Some(context) = context() else { return };
+    let mut app = MyApp::new(&context, &[]);
+    app.sphere_center = [0.0, -1000.0, 0.0]; // no collision
+    let start = gpu_positions(&app, &context);
+
+    // after an odd number of steps the last state is in the second buffers, the checkpoint has to read those
+    app.step(&context, DELTA_TIME);
+    let checkpoint = app.checkpoint(&context);
+    for ((vertex, velocity), start) in checkpoint.vertices.iter().zip(&checkpoint.velocities).zip(&start) {
+        assert!((velocity.velocity[1] + GRAVITY * DELTA_TIME).abs() < 1e-5, "velocity {:?}", velocity.velocity);
+        assert!((vertex.position[1] - (start.y - GRAVITY * DELTA_TIME * DELTA_TIME)).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn a_point_cache_of_another_cloth_is_played_with_its_own_springs() {
+    let _gpu = GPU.lock().unwrap_or_else(|error| error.into_inner());
+    let Some(context) = context() else { return };
+
+    // one cloth smaller and one larger than the grid of main.rs
+    for (rows, columns) in [(4, 3), (CLOTH_ROWS + 3, CLOTH_COLUMNS + 2)] {
+        let grid = ClothGridBuilder::new(rows, columns);
+        let vertices = grid.vertices();
+        let path = std::env::temp_dir().join(format!("cloth_playback_{}x{}_{}.cache", rows, columns, std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let mut writer = point_cache::PointCacheWriter::create(&path, &vertices, &grid.indices(), point_cache::FLAG_VELOCITIES, 1).unwrap();
+        let positions: Vec<[f32; 3]> = vertices.iter().map(|vertex| vertex.position).collect();
+        for frame in 0..2 {
+            writer.write_frame(frame as f32 / 60.0, &positions, &vec![[0.0, -1.0, 0.0]; vertices.len()]).unwrap();
+        }
+        drop(writer);
+
+        let mut app = MyApp::new(&context, &["--play".to_string(), path.clone()]);
+        std::fs::remove_file(&path).unwrap();
+        let checkpoint = app.checkpoint(&context);
+        assert_eq!(checkpoint.vertices.len(), vertices.len());
+        assert_eq!(app.spring_offsets.len(), vertices.len() + 1);
+        for spring in &checkpoint.springs[..*app.spring_offsets.last().unwrap() as usize] {
+            assert!((spring.inital_index as usize) < vertices.len() && (spring.linked_index as usize) < vertices.len(), "a spring goes to a vertex of the grid, not of the cache");
+        }
+
+        // the passes that read the springs cover the vertices of the cache
+        app.play_cache(&context, 1.0 / 60.0);
+        app.compute_strain(&context);
+        app.measure_diagnostics(&context);
+    }
+}
@@ -0,0 +1,118 @@
+use std::ops::Range;
+
+use wgpu_bootstrap::wgpu;
+
+use crate::Spring;
+
+const WORKGROUP_SIZE: u32 = 128; // the same as the compute shaders
+
+// a storage buffer binding is limited to max_storage_buffer_binding_size (128 MiB by default, about 680x680 vertices with 12 springs each),
+// so the vertices are cut in chunks whose springs fit in one binding of the springs buffer and the passes that read the springs run once per chunk.
+// uniform of those passes, the same layout as SpringChunk in the shaders
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SpringChunk {
+    pub first_vertex: u32, // the chunk runs the vertices first_vertex..end_vertex
+    pub end_vertex: u32,
+    pub first_spring: u32, // the binding starts at this spring, springsR[0] in the shaders
+    pub end_spring: u32, // and ends before this one
+}
+
+impl SpringChunk {
+    // the springs of the vertices of the chunk, a few springs of the previous chunk can come before them in the binding
+    pub fn springs(&self, spring_offsets: &[u32]) -> Range<u32> {
+        spring_offsets[self.first_vertex as usize]..spring_offsets[self.end_vertex as usize]
+    }
+
+    pub fn number_workgroups(&self) -> u32 {
+        (self.end_vertex - self.first_vertex).div_ceil(WORKGROUP_SIZE)
+    }
+
+    pub fn springs_binding<'a>(&self, springs_buffer: &'a wgpu::Buffer) -> wgpu::BindingResource<'a> {
+        let spring_size = std::mem::size_of::<Spring>() as u64;
+        wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: springs_buffer,
+            offset: self.first_spring as u64 * spring_size,
+            size: wgpu::BufferSize::new((self.end_spring - self.first_spring) as u64 * spring_size),
+        })
+    }
+}
+
+// cut the vertices in chunks of whole workgroups (the diagnostics write one partial sum per workgroup) whose springs fit in max_binding_size bytes.
+// a binding has to start on a multiple of alignment bytes, so a chunk may also bind the last springs of the previous one
+pub fn spring_chunks(spring_offsets: &[u32], max_binding_size: u64, alignment: u64) -> Vec<SpringChunk> {
+    let spring_size = std::mem::size_of::<Spring>() as u64;
+    let max_springs = (max_binding_size / spring_size) as u32;
+    let spring_step = (1..).find(|springs: &u64| (springs * spring_size).is_multiple_of(alignment)).unwrap() as u32;
+    let number_vertices = (spring_offsets.len() - 1) as u32;
+    let last_spring = spring_offsets[number_vertices as usize].max(1) - 1;
+
+    let mut chunks = Vec::new();
+    let mut first_vertex = 0;
+    while first_vertex < number_vertices {
+        let first_spring = spring_offsets[first_vertex as usize].min(last_spring) / spring_step * spring_step;
+        let mut end_vertex = first_vertex;
+        loop {
+            let next_vertex = (end_vertex + WORKGROUP_SIZE).min(number_vertices);
+            if next_vertex == end_vertex || spring_offsets[next_vertex as usize] - first_spring > max_springs {
+                break;
+            }
+            end_vertex = next_vertex;
+        }
+        assert!(end_vertex > first_vertex, "the springs of {} vertices don't fit in a storage buffer binding of {} bytes", WORKGROUP_SIZE, max_binding_size);
+        chunks.push(SpringChunk {
+            first_vertex,
+            end_vertex,
+            first_spring,
+            end_spring: spring_offsets[end_vertex as usize].max(first_spring + 1),
+        });
+        first_vertex = end_vertex;
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPRING_SIZE: u64 = std::mem::size_of::<Spring>() as u64;
+
+    // offsets of number_vertices vertices with springs_per_vertex springs each
+    fn offsets(number_vertices: u32, springs_per_vertex: u32) -> Vec<u32> {
+        (0..=number_vertices).map(|vertex| vertex * springs_per_vertex).collect()
+    }
+
+    #[test]
+    fn a_small_cloth_is_one_chunk() {
+        let chunks = spring_chunks(&offsets(625, 12), 128 << 20, 256);
+        assert_eq!(chunks, vec![SpringChunk { first_vertex: 0, end_vertex: 625, first_spring: 0, end_spring: 7500 }]);
+    }
+
+    #[test]
+    fn chunks_cover_every_vertex_and_fit_in_a_binding() {
+        for (number_vertices, max_binding_size, alignment) in [(1000, 100_000, 256), (5000, 100_000, 16), (1 << 20, 128 << 20, 256), (129, 12 * 128 * SPRING_SIZE, 256)] {
+            let spring_offsets = offsets(number_vertices, 12);
+            let chunks = spring_chunks(&spring_offsets, max_binding_size, alignment);
+            assert!(chunks.len() > 1 || number_vertices < 1000);
+            assert_eq!(chunks[0].first_vertex, 0);
+            assert_eq!(chunks.last().unwrap().end_vertex, number_vertices);
+            for pair in chunks.windows(2) {
+                assert_eq!(pair[0].end_vertex, pair[1].first_vertex);
+            }
+            for chunk in &chunks {
+                assert!(chunk.first_vertex.is_multiple_of(WORKGROUP_SIZE));
+                assert!((chunk.first_spring as u64 * SPRING_SIZE).is_multiple_of(alignment));
+                assert!((chunk.end_spring - chunk.first_spring) as u64 * SPRING_SIZE <= max_binding_size);
+                let springs = chunk.springs(&spring_offsets);
+                assert!(chunk.first_spring <= springs.start && springs.end <= chunk.end_spring);
+            }
+        }
+    }
+
+    #[test]
+    fn a_cloth_without_springs_binds_the_padding_spring() {
+        // with the membrane and the dihedral bending there is no spring, the buffer only holds one zeroed spring
+        let chunks = spring_chunks(&vec![0; 301], 128 << 20, 256);
+        assert_eq!(chunks, vec![SpringChunk { first_vertex: 0, end_vertex: 300, first_spring: 0, end_spring: 1 }]);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::checkpoint::Checkpoint;
+use crate::headless::HeadlessContext;
+use crate::MyApp;
+
+const DELTA_TIME: f32 = 1.0 / 60.0; // fixed, like the headless rendering
+
+pub struct HashOptions {
+    pub steps: u32,
+    pub software: bool, // ask for the fallback (CPU) adapter, as --headless
+}
+
+// "--hash <steps>" runs that many steps without window and prints a hash of the state,
+// two runs on the same adapter and the same build give the same hash, a change of the hash is a change of the simulation
+pub const OPTIONS: &[(&str, bool)] = &[("--hash", true), ("--software", false)];
+
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Option<HashOptions> {
+    let mut steps = None;
+    let mut software = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--hash" => steps = Some(args.next().and_then(|value| value.parse().ok()).expect("--hash needs a number of steps")),
+            "--software" => software = true,
+            _ => {}
+        }
+    }
+    steps.map(|steps| HashOptions { steps, software })
+}
+
+// FNV-1a, the hash of std can change between versions of Rust
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+// hash of the positions, the velocities and the springs (torn types and plastic rest lengths), bit for bit
+pub fn hash(checkpoint: &Checkpoint) -> u64 {
+    let hash = fnv1a(0xcbf29ce484222325, bytemuck::cast_slice(&checkpoint.vertices));
+    let hash = fnv1a(hash, bytemuck::cast_slice(&checkpoint.velocities));
+    fnv1a(hash, bytemuck::cast_slice(&checkpoint.springs))
+}
+
+pub fn run(options: &HashOptions, args: &[String]) -> Result<u64, String> {
+    let context = HeadlessContext::new(64, 64, options.software)?;
+    let mut app = MyApp::new(&context, args);
+    for _ in 0..options.steps {
+        app.step(&context, DELTA_TIME);
+    }
+    Ok(hash(&app.checkpoint(&context)))
+}
@@ -0,0 +1,112 @@
+struct Spring {
+    vertex_index_1: u32,
+    vertex_index_2: u32,
+    rest_length: f32,
+    stiffness: f32,
+    damping: f32,
+    spring_type: u32,
+}
+
+struct ComputeData {
+    delta_time: f32,
+    nb_vertices: u32,
+    sphere_radius: f32,
+    sphere_center_x: f32,
+    sphere_center_y: f32,
+    sphere_center_z: f32,
+    vertex_mass: f32,
+    grab_index: i32,
+    grab_target_x: f32,
+    grab_target_y: f32,
+    grab_target_z: f32,
+    grab_stiffness: f32,
+    tear_ratio: f32,
+    plastic_yield: f32,
+    plastic_rate: f32,
+    dihedral_bending: u32,
+    membrane_fem: u32,
+    strain_limit: f32,
+}
+
+struct MembraneTriangle {
+    vertex_0: u32,
+    vertex_1: u32,
+    vertex_2: u32,
+    rest_inverse_00: f32,
+    rest_inverse_01: f32,
+    rest_inverse_10: f32,
+    rest_inverse_11: f32,
+    rest_area: f32,
+    lame_mu: f32,
+    lame_lambda: f32,
+    damping: f32,
+}
+
+let SPRING_TORN: u32 = 3u; // the same as in main.rs
+
+// the positions of the last step, only read like in diagnostics.wgsl
+@group(0) @binding(0) var<storage, read> verticiesPositions: array<vec4<f32>>;
+@group(1) @binding(0) var<uniform> data: ComputeData;
+@group(2) @binding(0) var<storage, read> springsR: array<Spring>;
+// the springs, triangles and hinges of each vertex, the same buffer as in forces_compute.wgsl
+@group(2) @binding(1) var<storage, read> vertexElements: array<u32>;
+@group(2) @binding(2) var<storage, read_write> strains: array<f32>; // aussi le deuxième vertex buffer du tissu, pour la heat map de cloth.wgsl
+@group(2) @binding(4) var<storage, read> membraneTriangles: array<MembraneTriangle>;
+
+// the vertices of this dispatch and the springs bound in springsR, see spring_chunks.rs
+struct SpringChunk {
+    first_vertex: u32,
+    end_vertex: u32,
+    first_spring: u32, // springsR[0]
+    end_spring: u32,
+}
+@group(2) @binding(3) var<uniform> chunk: SpringChunk;
+
+fn vertex_position(index: u32) -> vec3<f32> {
+    return verticiesPositions[index].xyz;
+}
+
+// largest principal stretch of a triangle minus 1: F = Ds * Dm^-1 as in membrane_force, the stretches are the square roots
+// of the eigenvalues of F^T F
+fn triangle_strain(element: MembraneTriangle) -> f32 {
+    let x0 = vertex_position(element.vertex_0);
+    let deformed = mat2x3<f32>(vertex_position(element.vertex_1) - x0, vertex_position(element.vertex_2) - x0);
+    let rest_inverse = mat2x2<f32>(element.rest_inverse_00, element.rest_inverse_01, element.rest_inverse_10, element.rest_inverse_11);
+    let deformation = deformed * rest_inverse;
+    let stretch = transpose(deformation) * deformation;
+
+    let half_trace = 0.5 * (stretch[0][0] + stretch[1][1]);
+    let half_difference = 0.5 * (stretch[0][0] - stretch[1][1]);
+    let largest = half_trace + sqrt(half_difference * half_difference + stretch[0][1] * stretch[0][1]);
+    return sqrt(largest) - 1.0;
+}
+
+// strain of each vertex for the heat map: the largest stretch of its springs relative to their rest length, 0 if they are all compressed.
+// with the membrane it is the largest principal stretch of its triangles instead
+@compute @workgroup_size(128, 1, 1)
+fn main(@builtin(global_invocation_id) invocation: vec3<u32>) {
+    let param = invocation + vec3<u32>(chunk.first_vertex, 0u, 0u);
+    if (param.x >= chunk.end_vertex) {
+          return;
+    }
+
+    let position = vertex_position(param.x);
+    var strain = 0.0;
+    if data.membrane_fem != 0u {
+        let triangles_start = data.nb_vertices + 1u;
+        for (var i = vertexElements[triangles_start + param.x]; i < vertexElements[triangles_start + param.x + 1u]; i++) {
+            strain = max(strain, triangle_strain(membraneTriangles[vertexElements[i] / 4u]));
+        }
+    } else {
+        for (var spring_index = vertexElements[param.x]; spring_index < vertexElements[param.x + 1u]; spring_index++) {
+            let spring = springsR[spring_index - chunk.first_spring];
+            if spring.spring_type == SPRING_TORN {
+                continue;
+            }
+            let distance = length(position - vertex_position(spring.vertex_index_2));
+            strain = max(strain, distance / spring.rest_length - 1.0);
+        }
+    }
+
+    strains[param.x] = strain;
+}
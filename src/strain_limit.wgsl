@@ -0,0 +1,92 @@
+struct Spring {
+    vertex_index_1: u32,
+    vertex_index_2: u32,
+    rest_length: f32,
+    stiffness: f32,
+    damping: f32,
+    spring_type: u32,
+}
+
+struct Correction {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+struct ComputeData {
+    delta_time: f32,
+    nb_vertices: u32,
+    sphere_radius: f32,
+    sphere_center_x: f32,
+    sphere_center_y: f32,
+    sphere_center_z: f32,
+    vertex_mass: f32,
+    grab_index: i32,
+    grab_target_x: f32,
+    grab_target_y: f32,
+    grab_target_z: f32,
+    grab_stiffness: f32,
+    tear_ratio: f32,
+    plastic_yield: f32,
+    plastic_rate: f32,
+    dihedral_bending: u32,
+    membrane_fem: u32,
+    strain_limit: f32,
+}
+let SPRING_STRUCTURAL: u32 = 0u;
+
+@group(1) @binding(0) var<storage, read> verticiesPositions: array<vec4<f32>>; // the state written by this step, bound read-only here
+@group(2) @binding(0) var<uniform> data: ComputeData;
+@group(3) @binding(0) var<storage, read> springsR: array<Spring>;
+@group(3) @binding(1) var<storage, read> springOffsets: array<u32>;
+@group(3) @binding(2) var<storage, read_write> corrections: array<Correction>; // déplacement de chaque sommet, appliqué par strain_limit_apply.wgsl
+
+// the vertices of this dispatch and the springs bound in springsR, see spring_chunks.rs
+struct SpringChunk {
+    first_vertex: u32,
+    end_vertex: u32,
+    first_spring: u32, // springsR[0]
+    end_spring: u32,
+}
+@group(3) @binding(3) var<uniform> chunk: SpringChunk;
+
+fn vertex_position(index: u32) -> vec3<f32> {
+    return verticiesPositions[index].xyz;
+}
+
+// one Jacobi iteration of the strain limiting: each structural spring longer or shorter than strain_limit * rest_length
+// asks both of its vertices to move half of the way back to the limit, a vertex takes the mean of what its springs ask.
+// the positions are only read here so every vertex sees the same state
+@compute @workgroup_size(128, 1, 1)
+fn main(@builtin(global_invocation_id) invocation: vec3<u32>) {
+    let param = invocation + vec3<u32>(chunk.first_vertex, 0u, 0u);
+    if (param.x >= chunk.end_vertex) {
+          return;
+    }
+
+    let position = vertex_position(param.x);
+    var correction = vec3<f32>(0.0, 0.0, 0.0);
+    var number_constraints = 0u;
+    for (var spring_index = springOffsets[param.x]; spring_index < springOffsets[param.x + 1u]; spring_index++) {
+        let spring = springsR[spring_index - chunk.first_spring];
+        if spring.spring_type != SPRING_STRUCTURAL {
+            continue;
+        }
+
+        let offset = position - vertex_position(spring.vertex_index_2);
+        let distance = length(offset);
+        let limited_distance = clamp(distance, (1.0 - data.strain_limit) * spring.rest_length, (1.0 + data.strain_limit) * spring.rest_length);
+        if distance > 0.0 && limited_distance != distance {
+            correction += 0.5 * (limited_distance - distance) * offset / distance;
+            number_constraints += 1u;
+        }
+    }
+
+    if number_constraints > 0u {
+        correction /= f32(number_constraints);
+    }
+
+    corrections[param.x].x = correction.x;
+    corrections[param.x].y = correction.y;
+    corrections[param.x].z = correction.z;
+}
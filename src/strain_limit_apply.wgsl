@@ -0,0 +1,46 @@
+struct Correction {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+struct ComputeData {
+    delta_time: f32,
+    nb_vertices: u32,
+    sphere_radius: f32,
+    sphere_center_x: f32,
+    sphere_center_y: f32,
+    sphere_center_z: f32,
+    vertex_mass: f32,
+    grab_index: i32,
+    grab_target_x: f32,
+    grab_target_y: f32,
+    grab_target_z: f32,
+    grab_stiffness: f32,
+    tear_ratio: f32,
+    plastic_yield: f32,
+    plastic_rate: f32,
+    dihedral_bending: u32,
+    membrane_fem: u32,
+    strain_limit: f32,
+}
+@group(1) @binding(0) var<storage, read_write> verticiesPositions: array<vec4<f32>>; // the state written by this step, corrected in place
+@group(1) @binding(1) var<storage, read_write> verticiesVelocities: array<vec4<f32>>;
+@group(2) @binding(0) var<uniform> data: ComputeData;
+@group(3) @binding(2) var<storage, read> corrections: array<Correction>;
+
+// moves each vertex by the correction of strain_limit.wgsl, the velocity follows so the cloth doesn't spring back next step
+@compute @workgroup_size(128, 1, 1)
+fn main(@builtin(global_invocation_id) param: vec3<u32>) {
+    if (param.x >= data.nb_vertices) {
+          return;
+    }
+
+    let correction = vec3<f32>(corrections[param.x].x, corrections[param.x].y, corrections[param.x].z);
+
+    verticiesPositions[param.x] += vec4<f32>(correction, 0.0);
+
+    if data.delta_time > 0.0 {
+        verticiesVelocities[param.x] += vec4<f32>(correction / data.delta_time, 0.0);
+    }
+}
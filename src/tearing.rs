@@ -0,0 +1,74 @@
+use crate::{Spring, SPRING_BEND, SPRING_TORN};
+
+// the springs of a vertex are springs[offsets[vertex]..offsets[vertex + 1]]
+fn vertex_springs<'a>(springs: &'a [Spring], offsets: &[u32], vertex: usize) -> &'a [Spring] {
+    &springs[offsets[vertex] as usize..offsets[vertex + 1] as usize]
+}
+
+// structural and shear springs hold the cloth together, bend springs only resist folding
+fn is_stretch(spring: &Spring) -> bool {
+    spring.spring_type != SPRING_BEND && spring.spring_type != SPRING_TORN
+}
+
+// true if a structural or shear spring still links the two vertices
+fn is_linked(springs: &[Spring], offsets: &[u32], vertex_1: usize, vertex_2: usize) -> bool {
+    vertex_springs(springs, offsets, vertex_1)
+        .iter()
+        .any(|spring| is_stretch(spring) && spring.linked_index as usize == vertex_2)
+}
+
+// true if a vertex is still linked to both vertices (a bend spring jumps over such a vertex)
+fn share_neighbour(springs: &[Spring], offsets: &[u32], vertex_1: usize, vertex_2: usize) -> bool {
+    vertex_springs(springs, offsets, vertex_1)
+        .iter()
+        .filter(|spring| is_stretch(spring))
+        .any(|spring| is_linked(springs, offsets, spring.linked_index as usize, vertex_2))
+}
+
+// the GPU only tears the spring of the vertex that saw the stretch, here we make the rest of the cloth agree:
+// the spring going the other way is torn too, and a bend spring is torn when its two vertices no longer share a neighbour.
+// returns true if a spring was changed
+pub fn propagate_tears(springs: &mut [Spring], offsets: &[u32]) -> bool {
+    let mut changed = false;
+
+    for spring_index in 0..springs.len() {
+        let spring = springs[spring_index];
+        let vertex = spring.inital_index as usize;
+        let linked = spring.linked_index as usize;
+
+        let torn = match spring.spring_type {
+            SPRING_TORN => continue,
+            SPRING_BEND => !share_neighbour(springs, offsets, vertex, linked),
+            _ => !is_linked(springs, offsets, linked, vertex),
+        };
+
+        if torn {
+            springs[spring_index].spring_type = SPRING_TORN;
+            changed = true;
+        }
+    }
+
+    changed
+}
+
+// triangles with a torn edge are collapsed into a single point so they are no longer drawn, the index buffer keeps its size.
+// returns true if a triangle was removed
+pub fn remove_torn_triangles(indices: &mut [u32], springs: &[Spring], offsets: &[u32]) -> bool {
+    let mut changed = false;
+
+    for triangle in indices.chunks_exact_mut(3) {
+        if triangle[0] == triangle[1] && triangle[1] == triangle[2] {
+            continue; // already removed
+        }
+        let torn = (0..3).any(|edge| {
+            !is_linked(springs, offsets, triangle[edge] as usize, triangle[(edge + 1) % 3] as usize)
+        });
+        if torn {
+            triangle[1] = triangle[0];
+            triangle[2] = triangle[0];
+            changed = true;
+        }
+    }
+
+    changed
+}